@@ -0,0 +1,6 @@
+use proc_macro::TokenStream;
+
+#[proc_macro_attribute]
+pub fn method(_attr: TokenStream, _item: TokenStream) -> TokenStream {
+    todo!()
+}
@@ -0,0 +1,1164 @@
+//! The parsed form of a proto file.
+//!
+//! Every node can be serialized with serde. The JSON form is stable & described by the schema in
+//! [`JSON_SCHEMA`] (`docs/ast.schema.json`): struct fields keep their Rust names, enums like
+//! [`ProtoConstant`] & [`ProtoFieldType`] are written as `{"kind": ..., "value": ...}` & spans
+//! use zero based lines & columns.
+
+use serde::{Deserialize, Serialize};
+
+use crate::span::Span;
+
+/// The JSON schema of a serialized [`ProtoFile`]
+pub const JSON_SCHEMA: &str = include_str!("../docs/ast.schema.json");
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoFile {
+    pub(crate) syntax: ProtoSyntax,
+    pub(crate) package: Vec<String>,
+    /// The `package` statement, empty when the file doesn't have one
+    pub(crate) package_span: Span,
+    pub(crate) imports: Vec<ProtoImport>,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) messages: Vec<ProtoMessage>,
+    pub(crate) enums: Vec<ProtoEnum>,
+    pub(crate) services: Vec<ProtoService>,
+    pub(crate) extends: Vec<ProtoExtend>,
+    /// The comments before the `syntax` statement, like a licence header
+    pub(crate) comments: Vec<String>,
+}
+
+impl ProtoFile {
+    pub fn syntax(&self) -> &ProtoSyntax {
+        &self.syntax
+    }
+
+    /// The comments before the `syntax` statement, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The components of the package name, `foo.bar.v1` is `["foo", "bar", "v1"]`
+    pub fn package(&self) -> &[String] {
+        &self.package
+    }
+
+    /// The span of the `package` statement, empty when the file doesn't have one
+    pub fn package_span(&self) -> Span {
+        self.package_span
+    }
+
+    pub fn imports(&self) -> &[ProtoImport] {
+        &self.imports
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn messages(&self) -> &[ProtoMessage] {
+        &self.messages
+    }
+
+    pub fn enums(&self) -> &[ProtoEnum] {
+        &self.enums
+    }
+
+    pub fn services(&self) -> &[ProtoService] {
+        &self.services
+    }
+
+    pub fn extends(&self) -> &[ProtoExtend] {
+        &self.extends
+    }
+
+    pub fn package_mut(&mut self) -> &mut Vec<String> {
+        &mut self.package
+    }
+
+    pub fn imports_mut(&mut self) -> &mut Vec<ProtoImport> {
+        &mut self.imports
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+
+    pub fn messages_mut(&mut self) -> &mut Vec<ProtoMessage> {
+        &mut self.messages
+    }
+
+    pub fn enums_mut(&mut self) -> &mut Vec<ProtoEnum> {
+        &mut self.enums
+    }
+
+    pub fn services_mut(&mut self) -> &mut Vec<ProtoService> {
+        &mut self.services
+    }
+
+    pub fn extends_mut(&mut self) -> &mut Vec<ProtoExtend> {
+        &mut self.extends
+    }
+}
+
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtoSyntax {
+    Proto2,
+    Proto3,
+}
+
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProtoImportType {
+    Default,
+    Weak,
+    Public,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoImport {
+    pub(crate) r#type: ProtoImportType,
+    pub(crate) path: String,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+}
+
+impl ProtoImport {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub fn import_type(&self) -> &ProtoImportType {
+        &self.r#type
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn path_mut(&mut self) -> &mut String {
+        &mut self.path
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoOption {
+    pub(crate) name: String,
+    pub(crate) value: ProtoConstant,
+    pub(crate) span: Span,
+    /// Empty for options within `[...]`
+    pub(crate) comments: Vec<String>,
+}
+
+impl ProtoOption {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before an `option` statement, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The option name as written, custom options keep their parentheses,
+    /// `(validate.rules).string.uuid`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &ProtoConstant {
+        &self.value
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn value_mut(&mut self) -> &mut ProtoConstant {
+        &mut self.value
+    }
+}
+
+/// The value of an option
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ProtoConstant {
+    String(String),
+    /// Ints cover the range of both `int64` & `uint64`, values outside of both are serialized as
+    /// strings
+    #[serde(with = "int")]
+    Int(i128),
+    /// Infinite & NaN values are serialized as the strings `inf`, `-inf` & `nan`
+    #[serde(with = "float")]
+    Float(f64),
+    Bool(bool),
+    /// A string whose escapes don't decode to valid UTF-8, `"\xff"`
+    Bytes(Vec<u8>),
+    /// Enum values or other identifiers, `SPEED` in `option optimize_for = SPEED;`
+    Identifier(String),
+    /// A text format message body, `{ get: "/v1/foo" }`
+    Aggregate(Vec<ProtoAggregateField>),
+    /// A list of values within an aggregate, `[1, 2, 3]`
+    List(Vec<ProtoConstant>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoAggregateField {
+    pub(crate) name: String,
+    pub(crate) value: ProtoConstant,
+}
+
+impl ProtoAggregateField {
+    /// The field name, extensions keep their brackets, `[foo.bar]`
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn value(&self) -> &ProtoConstant {
+        &self.value
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoMessage {
+    pub(crate) name: String,
+    pub(crate) fields: Vec<MessageField>,
+    pub(crate) oneofs: Vec<ProtoOneOf>,
+    pub(crate) messages: Vec<ProtoMessage>,
+    pub(crate) enums: Vec<ProtoEnum>,
+    pub(crate) extends: Vec<ProtoExtend>,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) reserved_ranges: Vec<ProtoRange>,
+    pub(crate) reserved_names: Vec<String>,
+    pub(crate) extension_ranges: Vec<ProtoRange>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+    pub(crate) trailing_comments: Vec<String>,
+}
+
+impl ProtoMessage {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The comments after the last declaration in the body, before the closing `}`
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.trailing_comments
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The fields declared directly within the message, fields within a oneof are part of the
+    /// oneof instead
+    pub fn fields(&self) -> &[MessageField] {
+        &self.fields
+    }
+
+    pub fn oneofs(&self) -> &[ProtoOneOf] {
+        &self.oneofs
+    }
+
+    /// Messages nested within this message
+    pub fn messages(&self) -> &[ProtoMessage] {
+        &self.messages
+    }
+
+    pub fn enums(&self) -> &[ProtoEnum] {
+        &self.enums
+    }
+
+    pub fn extends(&self) -> &[ProtoExtend] {
+        &self.extends
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn reserved_ranges(&self) -> &[ProtoRange] {
+        &self.reserved_ranges
+    }
+
+    pub fn reserved_names(&self) -> &[String] {
+        &self.reserved_names
+    }
+
+    /// The field numbers declared with `extensions`
+    pub fn extension_ranges(&self) -> &[ProtoRange] {
+        &self.extension_ranges
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn fields_mut(&mut self) -> &mut Vec<MessageField> {
+        &mut self.fields
+    }
+
+    pub fn oneofs_mut(&mut self) -> &mut Vec<ProtoOneOf> {
+        &mut self.oneofs
+    }
+
+    pub fn messages_mut(&mut self) -> &mut Vec<ProtoMessage> {
+        &mut self.messages
+    }
+
+    pub fn enums_mut(&mut self) -> &mut Vec<ProtoEnum> {
+        &mut self.enums
+    }
+
+    pub fn extends_mut(&mut self) -> &mut Vec<ProtoExtend> {
+        &mut self.extends
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+
+    pub fn reserved_ranges_mut(&mut self) -> &mut Vec<ProtoRange> {
+        &mut self.reserved_ranges
+    }
+
+    pub fn reserved_names_mut(&mut self) -> &mut Vec<String> {
+        &mut self.reserved_names
+    }
+
+    pub fn extension_ranges_mut(&mut self) -> &mut Vec<ProtoRange> {
+        &mut self.extension_ranges
+    }
+}
+
+/// An inclusive range of field or enum value numbers, `max` is stored as the largest number
+/// allowed where it was used. Extension ranges declared together share the options set on them,
+/// reserved ranges have none.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoRange {
+    pub(crate) start: i64,
+    pub(crate) end: i64,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) span: Span,
+}
+
+impl ProtoRange {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn start(&self) -> i64 {
+        self.start
+    }
+
+    pub fn end(&self) -> i64 {
+        self.end
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn contains(&self, number: i64) -> bool {
+        (self.start..=self.end).contains(&number)
+    }
+
+    pub fn start_mut(&mut self) -> &mut i64 {
+        &mut self.start
+    }
+
+    pub fn end_mut(&mut self) -> &mut i64 {
+        &mut self.end
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoOneOf {
+    pub(crate) name: String,
+    pub(crate) fields: Vec<MessageField>,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+    pub(crate) trailing_comments: Vec<String>,
+}
+
+impl ProtoOneOf {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The comments after the last declaration in the body, before the closing `}`
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.trailing_comments
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn fields(&self) -> &[MessageField] {
+        &self.fields
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn fields_mut(&mut self) -> &mut Vec<MessageField> {
+        &mut self.fields
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoEnum {
+    pub(crate) name: String,
+    pub(crate) values: Vec<ProtoEnumValue>,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) reserved_ranges: Vec<ProtoRange>,
+    pub(crate) reserved_names: Vec<String>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+    pub(crate) trailing_comments: Vec<String>,
+}
+
+impl ProtoEnum {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The comments after the last declaration in the body, before the closing `}`
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.trailing_comments
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn values(&self) -> &[ProtoEnumValue] {
+        &self.values
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn reserved_ranges(&self) -> &[ProtoRange] {
+        &self.reserved_ranges
+    }
+
+    pub fn reserved_names(&self) -> &[String] {
+        &self.reserved_names
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn values_mut(&mut self) -> &mut Vec<ProtoEnumValue> {
+        &mut self.values
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+
+    pub fn reserved_ranges_mut(&mut self) -> &mut Vec<ProtoRange> {
+        &mut self.reserved_ranges
+    }
+
+    pub fn reserved_names_mut(&mut self) -> &mut Vec<String> {
+        &mut self.reserved_names
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoEnumValue {
+    pub(crate) name: String,
+    pub(crate) number: i32,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+}
+
+impl ProtoEnumValue {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn number(&self) -> i32 {
+        self.number
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn number_mut(&mut self) -> &mut i32 {
+        &mut self.number
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoService {
+    pub(crate) name: String,
+    pub(crate) rpcs: Vec<ProtoRpc>,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+    pub(crate) trailing_comments: Vec<String>,
+}
+
+impl ProtoService {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The comments after the last declaration in the body, before the closing `}`
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.trailing_comments
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn rpcs(&self) -> &[ProtoRpc] {
+        &self.rpcs
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn rpcs_mut(&mut self) -> &mut Vec<ProtoRpc> {
+        &mut self.rpcs
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoRpc {
+    pub(crate) name: String,
+    pub(crate) request: ProtoRpcType,
+    pub(crate) returns: ProtoRpcType,
+    pub(crate) options: Vec<ProtoOption>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+}
+
+impl ProtoRpc {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn request(&self) -> &ProtoRpcType {
+        &self.request
+    }
+
+    pub fn returns(&self) -> &ProtoRpcType {
+        &self.returns
+    }
+
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn request_mut(&mut self) -> &mut ProtoRpcType {
+        &mut self.request
+    }
+
+    pub fn returns_mut(&mut self) -> &mut ProtoRpcType {
+        &mut self.returns
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+}
+
+/// The request or response of an rpc, `stream FooRequest`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoRpcType {
+    pub(crate) stream: bool,
+    pub(crate) r#type: ProtoFieldType,
+}
+
+impl ProtoRpcType {
+    pub fn is_stream(&self) -> bool {
+        self.stream
+    }
+
+    pub fn message_type(&self) -> &ProtoFieldType {
+        &self.r#type
+    }
+
+    pub fn message_type_mut(&mut self) -> &mut ProtoFieldType {
+        &mut self.r#type
+    }
+}
+
+/// `extend Foo { ... }`, adding extension fields to another message
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProtoExtend {
+    pub(crate) extendee: ProtoFieldType,
+    pub(crate) fields: Vec<MessageField>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+    pub(crate) trailing_comments: Vec<String>,
+}
+
+impl ProtoExtend {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    /// The comments after the last declaration in the body, before the closing `}`
+    pub fn trailing_comments(&self) -> &[String] {
+        &self.trailing_comments
+    }
+
+    /// The message being extended
+    pub fn extendee(&self) -> &ProtoFieldType {
+        &self.extendee
+    }
+
+    pub fn fields(&self) -> &[MessageField] {
+        &self.fields
+    }
+
+    pub fn extendee_mut(&mut self) -> &mut ProtoFieldType {
+        &mut self.extendee
+    }
+
+    pub fn fields_mut(&mut self) -> &mut Vec<MessageField> {
+        &mut self.fields
+    }
+}
+
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, Serialize, Deserialize)]
+#[strum(serialize_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum ProtoFieldLabel {
+    Optional,
+    Required,
+    Repeated,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageField {
+    pub(crate) label: Option<ProtoFieldLabel>,
+    pub(crate) r#type: ProtoFieldType,
+    pub(crate) name: String,
+    pub(crate) index: u32,
+    /// `[default = ...]`, kept out of `options` as it isn't a real option
+    pub(crate) default: Option<ProtoOption>,
+    /// `[json_name = "..."]`, kept out of `options` as it isn't a real option
+    pub(crate) json_name: Option<ProtoOption>,
+    pub(crate) options: Vec<ProtoOption>,
+    /// The message declared by a group, the field's type is its name
+    pub(crate) group: Option<ProtoMessage>,
+    pub(crate) span: Span,
+    pub(crate) comments: Vec<String>,
+}
+
+impl MessageField {
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// The comments directly before the declaration, without their `//` or `/* */`
+    pub fn comments(&self) -> &[String] {
+        &self.comments
+    }
+
+    pub fn label(&self) -> Option<ProtoFieldLabel> {
+        self.label
+    }
+
+    pub fn field_type(&self) -> &ProtoFieldType {
+        &self.r#type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The field number, `string name = 1;` has an index of 1
+    pub fn index(&self) -> u32 {
+        self.index
+    }
+
+    /// The `[default = ...]` pseudo-option as written
+    pub fn default(&self) -> Option<&ProtoOption> {
+        self.default.as_ref()
+    }
+
+    /// The default value converted to the field's type, or why it can't be
+    pub fn default_value(&self) -> Option<Result<ProtoDefault, String>> {
+        self.default
+            .as_ref()
+            .map(|default| ProtoDefault::new(&self.r#type, default.value()))
+    }
+
+    /// The `[json_name = "..."]` pseudo-option as written
+    pub fn json_name_option(&self) -> Option<&ProtoOption> {
+        self.json_name.as_ref()
+    }
+
+    /// The name used for the field in JSON, either set with `json_name` or derived from the field
+    /// name
+    pub fn json_name(&self) -> String {
+        match self.json_name.as_ref().map(ProtoOption::value) {
+            Some(ProtoConstant::String(name)) => name.clone(),
+            _ => to_json_name(&self.name),
+        }
+    }
+
+    /// The options within brackets after the field number,
+    /// `[(validate.rules).string.uuid = true]`, excluding `default` & `json_name`
+    pub fn options(&self) -> &[ProtoOption] {
+        &self.options
+    }
+
+    pub fn label_mut(&mut self) -> &mut Option<ProtoFieldLabel> {
+        &mut self.label
+    }
+
+    pub fn field_type_mut(&mut self) -> &mut ProtoFieldType {
+        &mut self.r#type
+    }
+
+    pub fn name_mut(&mut self) -> &mut String {
+        &mut self.name
+    }
+
+    pub fn index_mut(&mut self) -> &mut u32 {
+        &mut self.index
+    }
+
+    pub fn options_mut(&mut self) -> &mut Vec<ProtoOption> {
+        &mut self.options
+    }
+
+    /// The message declared by `optional group Foo = 1 { ... }`, `None` for other fields
+    pub fn group(&self) -> Option<&ProtoMessage> {
+        self.group.as_ref()
+    }
+
+    pub fn group_mut(&mut self) -> Option<&mut ProtoMessage> {
+        self.group.as_mut()
+    }
+
+    /// The name of the nested message protoc synthesizes for a map field, `map<string, Foo>
+    /// foo_bar` is stored as repeated `FooBarEntry` messages. `None` for other fields.
+    pub fn map_entry_name(&self) -> Option<String> {
+        if !matches!(self.r#type, ProtoFieldType::Map { .. }) {
+            return None;
+        }
+        let mut entry_name = String::with_capacity(self.name.len() + "Entry".len());
+        let mut capitalize_next = true;
+        for c in self.name.chars() {
+            if c == '_' {
+                capitalize_next = true;
+            } else if capitalize_next {
+                entry_name.push(c.to_ascii_uppercase());
+                capitalize_next = false;
+            } else {
+                entry_name.push(c);
+            }
+        }
+        entry_name.push_str("Entry");
+        Some(entry_name)
+    }
+}
+
+/// Derives the JSON name of a field the same way protoc does, underscores are removed & the
+/// character after each is made uppercase, `foo_bar_baz` becomes `fooBarBaz`
+pub fn to_json_name(name: &str) -> String {
+    let mut json_name = String::with_capacity(name.len());
+    let mut capitalize_next = false;
+    for c in name.chars() {
+        if c == '_' {
+            capitalize_next = true;
+        } else if capitalize_next {
+            json_name.push(c.to_ascii_uppercase());
+            capitalize_next = false;
+        } else {
+            json_name.push(c);
+        }
+    }
+    json_name
+}
+
+/// A field's default value converted to the field's type
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProtoDefault {
+    Bool(bool),
+    /// The value of any signed int type
+    Int(i64),
+    /// The value of any unsigned int type
+    Uint(u64),
+    Float(f64),
+    String(String),
+    Bytes(Vec<u8>),
+    /// The name of an enum value, whether the enum has it is only known once the type is resolved
+    Enum(String),
+}
+
+impl ProtoDefault {
+    /// Converts a default value for a field of `field_type`, describing the problem when it
+    /// doesn't fit the type
+    pub fn new(field_type: &ProtoFieldType, value: &ProtoConstant) -> Result<Self, String> {
+        use ProtoFieldType as T;
+
+        let int = |min: i128, max: i128| match value {
+            ProtoConstant::Int(i) if (min..=max).contains(i) => Ok(*i),
+            ProtoConstant::Int(i) => Err(format!(
+                "default value {i} is out of range for {}",
+                field_type.name()
+            )),
+            // The scanner reads decimal ints too large for a u64 as floats
+            ProtoConstant::Float(f) if f.fract() == 0.0 && f.abs() >= u64::MAX as f64 => Err(
+                format!("default value is out of range for {}", field_type.name()),
+            ),
+            _ => Err(format!(
+                "default value for a {} field must be an integer",
+                field_type.name()
+            )),
+        };
+
+        Ok(match field_type {
+            T::Int32 | T::Sint32 | T::Sfixed32 => {
+                Self::Int(int(i32::MIN.into(), i32::MAX.into())? as i64)
+            }
+            T::Int64 | T::Sint64 | T::Sfixed64 => {
+                Self::Int(int(i64::MIN.into(), i64::MAX.into())? as i64)
+            }
+            T::Uint32 | T::Fixed32 => Self::Uint(int(0, u32::MAX.into())? as u64),
+            T::Uint64 | T::Fixed64 => Self::Uint(int(0, u64::MAX.into())? as u64),
+            T::Float | T::Double => match value {
+                ProtoConstant::Float(f) => Self::Float(*f),
+                ProtoConstant::Int(i) => Self::Float(*i as f64),
+                _ => {
+                    return Err(format!(
+                        "default value for a {} field must be a number",
+                        field_type.name()
+                    ))
+                }
+            },
+            T::Bool => match value {
+                ProtoConstant::Bool(b) => Self::Bool(*b),
+                _ => return Err("default value for a bool field must be true or false".into()),
+            },
+            T::String => match value {
+                ProtoConstant::String(s) => Self::String(s.clone()),
+                ProtoConstant::Bytes(_) => {
+                    return Err("default value for a string field must be valid UTF-8".into())
+                }
+                _ => return Err("default value for a string field must be a string".into()),
+            },
+            T::Bytes => match value {
+                ProtoConstant::String(s) => Self::Bytes(s.clone().into_bytes()),
+                ProtoConstant::Bytes(b) => Self::Bytes(b.clone()),
+                _ => return Err("default value for a bytes field must be a string".into()),
+            },
+            T::Identifier(_) | T::FullIdentifier(_) => match value {
+                ProtoConstant::Identifier(name) if !name.contains('.') => Self::Enum(name.clone()),
+                _ => {
+                    return Err("default value for an enum field must be an enum value name".into())
+                }
+            },
+            T::Map { .. } => return Err("map fields can't have default values".into()),
+        })
+    }
+}
+
+#[derive(Debug, Clone, strum::Display, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum ProtoFieldType {
+    /// A dotted type name, absolute names like `.foo.Bar` start with an empty part
+    FullIdentifier(Vec<String>),
+    Identifier(String),
+    Bool,
+    String,
+    Bytes,
+    Float,
+    Double,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
+    Map {
+        key: Box<ProtoFieldType>,
+        value: Box<ProtoFieldType>,
+    },
+}
+
+impl ProtoFieldType {
+    /// The type as it's written in a proto file, `map<string, foo.Bar>`
+    pub fn name(&self) -> String {
+        match self {
+            Self::FullIdentifier(parts) => parts.join("."),
+            Self::Identifier(name) => name.clone(),
+            Self::Map { key, value } => format!("map<{}, {}>", key.name(), value.name()),
+            scalar => scalar.to_string().to_lowercase(),
+        }
+    }
+}
+
+// int (de)serializes ints as JSON numbers when they fit in an `i64` or `u64`, otherwise as strings
+mod int {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &i128, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Ok(value) = i64::try_from(*value) {
+            serializer.serialize_i64(value)
+        } else if let Ok(value) = u64::try_from(*value) {
+            serializer.serialize_u64(value)
+        } else {
+            serializer.serialize_str(&value.to_string())
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Int {
+        Signed(i64),
+        Unsigned(u64),
+        String(String),
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<i128, D::Error> {
+        match Int::deserialize(deserializer)? {
+            Int::Signed(value) => Ok(value.into()),
+            Int::Unsigned(value) => Ok(value.into()),
+            Int::String(value) => value.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+// float (de)serializes floats as JSON numbers, using strings for the values JSON can't represent
+mod float {
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(value: &f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if value.is_nan() {
+            serializer.serialize_str("nan")
+        } else if value.is_infinite() {
+            serializer.serialize_str(if *value > 0.0 { "inf" } else { "-inf" })
+        } else {
+            serializer.serialize_f64(*value)
+        }
+    }
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Float {
+        Number(f64),
+        String(String),
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+        let value = match Float::deserialize(deserializer)? {
+            Float::Number(value) => return Ok(value),
+            Float::String(value) => value,
+        };
+        match value.as_str() {
+            "nan" => Ok(f64::NAN),
+            "inf" => Ok(f64::INFINITY),
+            "-inf" => Ok(f64::NEG_INFINITY),
+            other => Err(serde::de::Error::invalid_value(
+                serde::de::Unexpected::Str(other),
+                &"a number, inf, -inf or nan",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"syntax = "proto2";
+package foo.v1;
+import weak "other.proto";
+option (custom) = { name: "a" [ext.value]: -inf list: [1, 2.5] };
+
+// Documents Outer
+message Outer {
+    reserved 2 to max;
+    extensions 100 to 200;
+    /* Documents a */
+    optional int32 a = 1 [default = nan];
+    map<string, Outer> children = 3;
+    oneof choice { bytes b = 4; }
+    enum Kind { KIND_UNKNOWN = 0; }
+    extend Outer { repeated uint64 ids = 101; }
+}
+
+service Svc {
+    rpc Get(stream Outer) returns (foo.v1.Outer);
+}
+"#;
+
+    #[test]
+    fn json_round_trip() {
+        let file = crate::parse(SOURCE).unwrap();
+        let json = serde_json::to_string(&file).unwrap();
+        let decoded: ProtoFile = serde_json::from_str(&json).unwrap();
+
+        // NaN never equals itself so compare the JSON rather than the files
+        assert_eq!(json, serde_json::to_string(&decoded).unwrap());
+        assert_eq!(file.messages()[0].span(), decoded.messages()[0].span());
+    }
+
+    #[test]
+    fn json_layout() {
+        let file = crate::parse(SOURCE).unwrap();
+        let json = serde_json::to_value(&file).unwrap();
+
+        assert_eq!("proto2", json["syntax"]);
+        assert_eq!("weak", json["imports"][0]["type"]);
+        assert_eq!(
+            serde_json::json!({"kind": "float", "value": "-inf"}),
+            json["options"][0]["value"]["value"][1]["value"]
+        );
+        let field = &json["messages"][0]["fields"][0];
+        assert_eq!(serde_json::json!([" Documents a "]), field["comments"]);
+        assert_eq!("optional", field["label"]);
+        assert_eq!(serde_json::json!({"kind": "int32"}), field["type"]);
+        assert_eq!(
+            serde_json::json!({"offset": 240, "line": 10, "column": 4}),
+            field["span"]["start"]
+        );
+        assert_eq!(
+            serde_json::json!({"kind": "map", "value": {
+                "key": {"kind": "string"},
+                "value": {"kind": "identifier", "value": "Outer"},
+            }}),
+            json["messages"][0]["fields"][1]["type"]
+        );
+    }
+
+    // Every key written out should be described by the schema
+    #[test]
+    fn json_schema_covers_keys() {
+        fn keys(value: &serde_json::Value, out: &mut Vec<String>) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    for (key, value) in map {
+                        out.push(key.clone());
+                        keys(value, out);
+                    }
+                }
+                serde_json::Value::Array(values) => values.iter().for_each(|v| keys(v, out)),
+                _ => {}
+            }
+        }
+
+        let schema: serde_json::Value = serde_json::from_str(JSON_SCHEMA).unwrap();
+        let mut properties = vec![];
+        keys(&schema, &mut properties);
+
+        let mut written = vec![];
+        keys(
+            &serde_json::to_value(crate::parse(SOURCE).unwrap()).unwrap(),
+            &mut written,
+        );
+        for key in written {
+            assert!(
+                properties.contains(&key),
+                "{key} is missing from the schema"
+            );
+        }
+    }
+}
@@ -0,0 +1,167 @@
+use std::fmt;
+
+use crate::span::Span;
+
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[strum(serialize_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+/// Identifies a rule so it can be looked up, displayed as `proto3-required`
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Code {
+    /// The first value of a proto3 enum must be zero so it can be the default
+    Proto3FirstEnumValue,
+    /// proto3 fields can't be `required`
+    Proto3Required,
+    /// proto3 doesn't support groups, use a nested message instead
+    Proto3Group,
+    /// proto3 fields can't set a `[default = ...]`
+    Proto3Default,
+    /// proto3 messages can't declare `extensions` ranges
+    Proto3Extensions,
+    /// proto3 files can only extend the `google.protobuf.*Options` messages
+    Proto3Extend,
+    /// proto3 fields can't use closed proto2 enums
+    Proto3ClosedEnum,
+}
+
+/// Extra information about a diagnostic, which editors show by striking deprecated names through
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "lowercase")]
+pub enum Tag {
+    /// The diagnostic is about a use of something marked `deprecated = true`
+    Deprecated,
+}
+
+/// A problem found within a proto file, such as a syntax error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    severity: Severity,
+    code: Option<Code>,
+    tag: Option<Tag>,
+    span: Span,
+    message: String,
+    related: Vec<Related>,
+}
+
+/// Another location involved in a diagnostic, such as the first declaration of a duplicate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Related {
+    file: Option<String>,
+    span: Span,
+    message: String,
+}
+
+impl Related {
+    /// The file the location is in, `None` when it's the same file as the diagnostic
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Diagnostic {
+    pub fn new(severity: Severity, span: Span, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            code: None,
+            tag: None,
+            span,
+            message: message.into(),
+            related: vec![],
+        }
+    }
+
+    /// Sets the rule the diagnostic comes from
+    pub fn with_code(mut self, code: Code) -> Self {
+        self.code = Some(code);
+        self
+    }
+
+    pub fn with_tag(mut self, tag: Tag) -> Self {
+        self.tag = Some(tag);
+        self
+    }
+
+    /// Adds a location within the same file to the diagnostic
+    pub fn with_related(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.related.push(Related {
+            file: None,
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    /// Adds a location within the file called `file` to the diagnostic
+    pub fn with_related_in(
+        mut self,
+        file: impl Into<String>,
+        span: Span,
+        message: impl Into<String>,
+    ) -> Self {
+        self.related.push(Related {
+            file: Some(file.into()),
+            span,
+            message: message.into(),
+        });
+        self
+    }
+
+    pub fn error(span: Span, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, span, message)
+    }
+
+    pub fn warning(span: Span, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, span, message)
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn code(&self) -> Option<Code> {
+        self.code
+    }
+
+    pub fn tag(&self) -> Option<Tag> {
+        self.tag
+    }
+
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn related(&self) -> &[Related] {
+        &self.related
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.span.start(), self.severity)?;
+        if let Some(code) = self.code {
+            write!(f, "[{code}]")?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl std::error::Error for Diagnostic {}
@@ -1,6 +1,13 @@
-use proc_macro::TokenStream;
+pub mod ast;
+pub mod diagnostics;
+pub mod parser;
+pub mod printer;
+pub mod span;
+pub mod symbols;
+pub mod syntax;
+pub mod validate;
+pub mod visit;
+pub mod well_known;
+pub mod workspace;
 
-#[proc_macro_attribute]
-pub fn method(_attr: TokenStream, _item: TokenStream) -> TokenStream {
-    todo!()
-}
+pub use parser::{parse, parse_file, ParsedFile};
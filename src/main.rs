@@ -1,14 +1,14 @@
 #![allow(dead_code)]
 mod language_server;
-mod parser;
 
 use std::{
     collections::HashMap,
-    fs::File,
     io::{stdin, BufRead, BufReader, Read},
+    path::Path,
 };
 
 use anyhow::{bail, Result};
+use proto_ls::workspace::Workspace;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -18,10 +18,19 @@ async fn main() -> Result<()> {
 }
 
 fn parse_and_log_file() {
-    let f = File::open("/Users/charliehowe/Projects/rust/proto_ls/test.proto").unwrap();
-    let mut sc = parser::scanner::Scanner::new(f);
-    let f = parser::scan_file(&mut sc).unwrap();
-    println!("{f:#?}");
+    let path = Path::new("/Users/charliehowe/Projects/rust/proto_ls/test.proto");
+    let mut workspace = Workspace::new(path.parent());
+    let name = workspace.file_name(path).unwrap();
+    if let Err(err) = workspace.load(&name) {
+        println!("{err}");
+        return;
+    }
+    for diagnostic in workspace.diagnostics(&name) {
+        println!("{name}:{diagnostic}");
+    }
+    if let Some(f) = workspace.file(&name) {
+        println!("{}", serde_json::to_string_pretty(f).unwrap());
+    }
 }
 
 // signal::ctrl_c().await?;
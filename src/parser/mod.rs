@@ -5,95 +5,105 @@ use std::{io::Read, vec};
 
 use anyhow::{bail, Result};
 
+use crate::ast::{
+    MessageField, ProtoAggregateField, ProtoConstant, ProtoEnum, ProtoEnumValue, ProtoExtend,
+    ProtoFieldLabel, ProtoFieldType, ProtoFile, ProtoImport, ProtoImportType, ProtoMessage,
+    ProtoOneOf, ProtoOption, ProtoRange, ProtoRpc, ProtoRpcType, ProtoService, ProtoSyntax,
+};
+use crate::diagnostics::{Diagnostic, Severity};
+use crate::span::{Position, Span};
+use crate::symbols::SymbolTable;
+use crate::validate;
 use scanner::Scanner;
 use tokens::ProtoToken;
 
-#[derive(Debug)]
-pub struct ProtoFile {
-    syntax: ProtoSyntax,
-    package: Vec<String>,
-    imports: Vec<ProtoImport>,
-    options: Vec<ProtoOption>,
-    messages: Vec<ProtoMessage>,
-    services: Vec<ProtoService>,
+/// Parses the source of a proto file, returning the first syntax error on failure. Use
+/// [`parse_file`] to keep every diagnostic & validate the file.
+pub fn parse(input: &str) -> Result<ProtoFile, Diagnostic> {
+    let (file, diagnostics) = parse_source(input);
+    if let Some(err) = diagnostics
+        .into_iter()
+        .find(|diagnostic| diagnostic.severity() == Severity::Error)
+    {
+        return Err(err);
+    }
+    Ok(file.expect("a file is parsed when there are no errors"))
 }
 
-#[derive(Debug, strum::Display, PartialEq)]
-enum ProtoSyntax {
-    Proto2,
-    Proto3,
-}
+/// Parses & validates `source` as the file `name`, which is usually a path or URI. Files without
+/// a `syntax` statement are parsed as proto2. Imports aren't loaded, so names which don't resolve
+/// within the file are only reported when it has no imports. Use a [`Workspace`] to resolve
+/// names through imports.
+///
+/// [`Workspace`]: crate::workspace::Workspace
+pub fn parse_file(name: impl Into<String>, source: &str) -> ParsedFile {
+    let name = name.into();
+    let (file, mut diagnostics) = parse_source(source);
+    if let Some(file) = &file {
+        let mut symbols = SymbolTable::new();
+        symbols.add_file(&name, file);
+        let mut visible = symbols.visible_to([&name]);
+        if !file.imports().is_empty() {
+            visible = visible.without_imports();
+        }
+        diagnostics.extend(validate::validate(file, &visible));
+    }
 
-#[derive(Debug, strum::Display, PartialEq)]
-enum ProtoImportType {
-    Default,
-    Weak,
-    Public,
+    ParsedFile {
+        name,
+        file,
+        diagnostics,
+    }
 }
 
-#[derive(Debug)]
-struct ProtoImport {
-    r#type: ProtoImportType,
-    path: String,
+pub(crate) fn parse_source(source: &str) -> (Option<ProtoFile>, Vec<Diagnostic>) {
+    let mut scan = Scanner::new(source.as_bytes());
+    let mut diagnostics = vec![];
+    let file = match scan_file(&mut scan, &mut diagnostics) {
+        Ok(file) => Some(file),
+        Err(err) => {
+            diagnostics.push(Diagnostic::error(scan.last_span(), err.to_string()));
+            None
+        }
+    };
+    (file, diagnostics)
 }
 
-#[derive(Debug)]
-struct ProtoOption {
+/// The result of [`parse_file`]
+#[derive(Debug, Clone)]
+pub struct ParsedFile {
     name: String,
-    value: String,
+    file: Option<ProtoFile>,
+    diagnostics: Vec<Diagnostic>,
 }
 
-#[derive(Debug)]
-struct ProtoMessage {
-    name: String,
-    fields: Vec<MessageField>,
-    messages: Vec<ProtoMessage>,
-}
+impl ParsedFile {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
 
-#[derive(Debug)]
-struct ProtoService {
-    name: String,
-    rpcs: Vec<ProtoRpc>,
-}
+    /// The parsed file, `None` when a syntax error stopped parsing
+    pub fn file(&self) -> Option<&ProtoFile> {
+        self.file.as_ref()
+    }
 
-#[derive(Debug)]
-struct ProtoRpc {
-    name: String,
-    params: Vec<String>,
-    returns: String,
-}
+    pub fn into_file(self) -> Option<ProtoFile> {
+        self.file
+    }
 
-// TODO: Adding options to a message field
-// string event_id = 1 [(validate.rules).string.uuid = true];
-// TODO: Handle a map type - map<string, string>
-#[derive(Debug)]
-struct MessageField {
-    r#type: ProtoFieldType,
-    name: String,
-    index: u16,
-}
+    pub fn diagnostics(&self) -> &[Diagnostic] {
+        &self.diagnostics
+    }
 
-#[derive(Debug)]
-pub struct PositionedProtoToken {
-    token: ProtoToken,
-    column: usize,
-    line: usize,
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity() == Severity::Error)
+    }
 }
 
-#[derive(Debug, strum::Display, PartialEq)]
-pub enum ProtoFieldType {
-    FullIdentifier(Vec<String>),
-    Identifier(String),
-    Bool,
-    String,
-    Bytes,
-    Float,
-    Double,
-    Map {
-        key: Box<ProtoFieldType>,
-        value: Box<ProtoFieldType>,
-    },
-}
+/// The largest field number, also used for `max` in message reserved & extension ranges
+pub(crate) const FIELD_NUMBER_MAX: i64 = 536_870_911;
 
 impl ProtoFieldType {
     fn from_token<T: Read>(t: ProtoToken, scan: &mut Scanner<T>) -> Result<Self> {
@@ -104,19 +114,45 @@ impl ProtoFieldType {
                     .collect(),
             ),
             ProtoToken::Identifier(id) => Self::Identifier(String::from_utf8(id)?),
+            ProtoToken::Dot => {
+                let mut parts = vec![String::new()];
+                match scan.next_token() {
+                    Some(ProtoToken::FullIdentifier(id)) => {
+                        for part in id {
+                            parts.push(String::from_utf8(part)?);
+                        }
+                    }
+                    Some(token) => match token.as_identifier() {
+                        Some(id) => parts.push(id),
+                        None => bail!("expected a type name but got {token}"),
+                    },
+                    None => bail!("expected a type name but received EOF"),
+                }
+                Self::FullIdentifier(parts)
+            }
             ProtoToken::Bool => Self::Bool,
             ProtoToken::String => Self::String,
             ProtoToken::Bytes => Self::Bytes,
             ProtoToken::Float => Self::Float,
             ProtoToken::Double => Self::Double,
+            ProtoToken::Int32 => Self::Int32,
+            ProtoToken::Int64 => Self::Int64,
+            ProtoToken::Uint32 => Self::Uint32,
+            ProtoToken::Uint64 => Self::Uint64,
+            ProtoToken::Sint32 => Self::Sint32,
+            ProtoToken::Sint64 => Self::Sint64,
+            ProtoToken::Fixed32 => Self::Fixed32,
+            ProtoToken::Fixed64 => Self::Fixed64,
+            ProtoToken::Sfixed32 => Self::Sfixed32,
+            ProtoToken::Sfixed64 => Self::Sfixed64,
             ProtoToken::Map => {
                 scan.expect(ProtoToken::LessThan)?;
-                let Some(key_token) = scan.next() else {
+                let Some(key_token) = scan.next_token() else {
                     bail!("expected a field token")
                 };
                 let key = Self::from_token(key_token, scan)?;
                 scan.expect(ProtoToken::Comma)?;
-                let Some(value_token) = scan.next() else {
+                let Some(value_token) = scan.next_token() else {
                     bail!("expected a field token")
                 };
                 let value = Self::from_token(value_token, scan)?;
@@ -126,108 +162,684 @@ impl ProtoFieldType {
                     value: Box::new(value),
                 }
             }
-            other => bail!("non proto field type {other}"),
+            // Keywords are only reserved where they're expected, so they can name types
+            other => match other.as_identifier() {
+                Some(name) => Self::Identifier(name),
+                None => bail!("non proto field type {other}"),
+            },
         })
     }
 }
 
-// proto = syntax { import | package | option | topLevelDef | emptyStatement }
-// topLevelDef = message | enum | service
-pub fn scan_file<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoFile> {
-    // TODO: Some of these errors can probably allow it to continue parsing (duplicate package) &
-    // just batch the errors up
-    let syntax = scan_syntax(scan)?;
+// proto = [syntax] { import | package | option | topLevelDef | emptyStatement }
+// topLevelDef = message | enum | service | extend
+// scan_file adds problems which don't stop parsing to diagnostics
+fn scan_file<T: Read>(
+    scan: &mut Scanner<T>,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Result<ProtoFile> {
+    let mut syntax = None;
+    let mut package_span: Option<Span> = None;
+    // Whether any statement other than an empty one has been scanned
+    let mut seen_statement = false;
+    // Whether any message, enum, service or extend has been scanned
+    let mut seen_definition = false;
 
     let mut imports = vec![];
     let mut options = vec![];
     let mut package = vec![];
     let mut messages = vec![];
+    let mut enums = vec![];
     let mut services = vec![];
+    let mut extends = vec![];
+    let mut comments = vec![];
 
-    while let Some(token) = scan.next() {
+    while let Some(token) = scan.next_token() {
+        let start = scan.last_span().start();
+        let is_definition = matches!(
+            token,
+            ProtoToken::Message | ProtoToken::Enum | ProtoToken::Service | ProtoToken::Extend
+        );
         match token {
-            ProtoToken::Comment(_) => {}
-            ProtoToken::SemiColon => {}
-            ProtoToken::Syntax => todo!(),
-            // TODO: Check we haven't already had a package
-            ProtoToken::Package => package = scan_package(scan)?,
+            ProtoToken::SemiColon => continue,
+            ProtoToken::Syntax => {
+                if !seen_statement {
+                    comments = scan.leading_comments().to_vec();
+                }
+                let scanned = scan_syntax(scan)?;
+                let span = Span::new(start, scan.last_span().end());
+                if syntax.is_some() {
+                    diagnostics.push(Diagnostic::error(span, "duplicate syntax statement"));
+                } else {
+                    if seen_statement {
+                        diagnostics.push(Diagnostic::error(
+                            span,
+                            "syntax must be the first statement in the file",
+                        ));
+                    }
+                    syntax = Some(scanned);
+                }
+            }
+            ProtoToken::Package => {
+                let scanned = scan_package(scan)?;
+                let span = Span::new(start, scan.last_span().end());
+                if let Some(first) = package_span {
+                    diagnostics.push(Diagnostic::error(
+                        span,
+                        format!(
+                            "duplicate package statement, first declared at {}",
+                            first.start()
+                        ),
+                    ));
+                } else {
+                    if seen_definition {
+                        diagnostics.push(Diagnostic::warning(
+                            span,
+                            "package should be declared before any definitions",
+                        ));
+                    }
+                    package_span = Some(span);
+                    package = scanned;
+                }
+            }
             ProtoToken::Option => options.push(scan_option(scan)?),
             ProtoToken::Import => imports.push(scan_import(scan)?),
             ProtoToken::Message => messages.push(scan_message(scan)?),
             ProtoToken::Service => services.push(scan_service(scan)?),
-            ProtoToken::Enum => todo!(),
+            ProtoToken::Enum => enums.push(scan_enum(scan)?),
+            ProtoToken::Extend => extends.push(scan_extend(scan)?),
             other => bail!("unexpected token {other}"),
         }
+        seen_statement = true;
+        seen_definition |= is_definition;
     }
 
+    let syntax = syntax.unwrap_or_else(|| {
+        diagnostics.push(Diagnostic::warning(
+            Span::default(),
+            "no syntax specified, defaulting to proto2",
+        ));
+        ProtoSyntax::Proto2
+    });
+
     Ok(ProtoFile {
         syntax,
         package: package
             .iter()
             .flat_map(|str| String::from_utf8(str.to_vec()))
             .collect(),
+        package_span: package_span.unwrap_or_default(),
         imports,
         options,
         messages,
+        enums,
         services,
+        extends,
+        comments,
     })
 }
 
+// node_start returns where the declaration whose first token was just scanned starts, along with
+// the comments before it
+fn node_start<T: Read>(scan: &Scanner<T>) -> (Position, Vec<String>) {
+    (scan.last_span().start(), scan.leading_comments().to_vec())
+}
+
+fn scan_name<T: Read>(scan: &mut Scanner<T>) -> Result<String> {
+    match scan.next_token().and_then(|token| token.as_identifier()) {
+        Some(name) => Ok(name),
+        None => bail!("expected identifier"),
+    }
+}
+
 // TODO: Write some helper funcs to make this all cleaner, better errors, store line num + char num
 fn scan_message<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoMessage> {
-    let mut messages = vec![];
-    let Some(ProtoToken::Identifier(name)) = scan.next() else {
-        bail!("expected identifier")
-    };
+    let (start, comments) = node_start(scan);
+    let name = scan_name(scan)?;
     scan.expect(ProtoToken::OpenBracket)?;
-    let mut fields = vec![];
-    while let Some(token) = scan.next() {
+    scan_message_body(scan, name, start, comments)
+}
+
+// scan_message_body scans the declarations of a message or group after its opening `{`
+fn scan_message_body<T: Read>(
+    scan: &mut Scanner<T>,
+    name: String,
+    start: Position,
+    comments: Vec<String>,
+) -> Result<ProtoMessage> {
+    let mut message = ProtoMessage {
+        name,
+        fields: vec![],
+        oneofs: vec![],
+        messages: vec![],
+        enums: vec![],
+        extends: vec![],
+        options: vec![],
+        reserved_ranges: vec![],
+        reserved_names: vec![],
+        extension_ranges: vec![],
+        span: Span::default(),
+        comments,
+        trailing_comments: vec![],
+    };
+    while let Some(token) = scan.next_token() {
         match token {
             ProtoToken::CloseBracket => {
-                break;
+                message.span = Span::new(start, scan.last_span().end());
+                message.trailing_comments = scan.leading_comments().to_vec();
+                return Ok(message);
             }
-            ProtoToken::Message => messages.push(scan_message(scan)?),
-            token => fields.push(scan_message_field(scan, token)?),
+            ProtoToken::SemiColon => {}
+            ProtoToken::Message => message.messages.push(scan_message(scan)?),
+            ProtoToken::Enum => message.enums.push(scan_enum(scan)?),
+            ProtoToken::OneOf => message.oneofs.push(scan_oneof(scan)?),
+            ProtoToken::Extend => message.extends.push(scan_extend(scan)?),
+            ProtoToken::Option => message.options.push(scan_option(scan)?),
+            ProtoToken::Reserved => scan_reserved(
+                scan,
+                FIELD_NUMBER_MAX,
+                &mut message.reserved_ranges,
+                &mut message.reserved_names,
+            )?,
+            ProtoToken::Extensions => {
+                let Some(first_token) = scan.next_token() else {
+                    bail!("expected an extension range")
+                };
+                let (mut ranges, end) = scan_ranges(scan, first_token, FIELD_NUMBER_MAX)?;
+                match end {
+                    ProtoToken::SemiColon => {}
+                    ProtoToken::OpenBrace => {
+                        let options = scan_option_list(scan)?;
+                        scan.expect(ProtoToken::SemiColon)?;
+                        for range in &mut ranges {
+                            range.options.clone_from(&options);
+                        }
+                    }
+                    other => bail!("wanted SemiColon but got {other}"),
+                }
+                message.extension_ranges.append(&mut ranges);
+            }
+            token => message.fields.push(scan_labelled_field(scan, token)?),
         };
     }
 
-    Ok(ProtoMessage {
-        name: String::from_utf8(name)?,
-        fields,
-        messages,
-    })
+    bail!("expected CloseBracket but received EOF")
+}
+
+// scan_labelled_field scans a message field which may start with a label
+fn scan_labelled_field<T: Read>(
+    scan: &mut Scanner<T>,
+    first_token: ProtoToken,
+) -> Result<MessageField> {
+    let start = node_start(scan);
+    let label = match first_token {
+        ProtoToken::Optional => Some(ProtoFieldLabel::Optional),
+        ProtoToken::Required => Some(ProtoFieldLabel::Required),
+        ProtoToken::Repeated => Some(ProtoFieldLabel::Repeated),
+        token => return scan_message_field(scan, start, None, token),
+    };
+    let Some(token) = scan.next_token() else {
+        bail!("expected a field type")
+    };
+    scan_message_field(scan, start, label, token)
 }
 
 fn scan_message_field<T: Read>(
     scan: &mut Scanner<T>,
+    (start, comments): (Position, Vec<String>),
+    label: Option<ProtoFieldLabel>,
     first_token: ProtoToken,
 ) -> Result<MessageField> {
+    if first_token == ProtoToken::Group {
+        return scan_group(scan, (start, comments), label);
+    }
     let r#type = ProtoFieldType::from_token(first_token, scan)?;
 
-    let Some(ProtoToken::Identifier(name)) = scan.next() else {
-        bail!("expected identifier/type")
-    };
+    let name = scan_name(scan)?;
+    scan.expect(ProtoToken::Equals)?;
+    let index = scan_field_number(scan)?;
+    let mut options = scan_field_options(scan)?;
+    let default = take_pseudo_option(&mut options, "default");
+    let json_name = take_pseudo_option(&mut options, "json_name");
+
+    Ok(MessageField {
+        label,
+        r#type,
+        name,
+        index,
+        default,
+        json_name,
+        options,
+        group: None,
+        span: Span::new(start, scan.last_span().end()),
+        comments,
+    })
+}
+
+// group = label "group" groupName "=" fieldNumber [ "[" fieldOptions "]" ] messageBody
+//
+// A group declares a message along with a field of that type named after it in lowercase.
+fn scan_group<T: Read>(
+    scan: &mut Scanner<T>,
+    (start, comments): (Position, Vec<String>),
+    label: Option<ProtoFieldLabel>,
+) -> Result<MessageField> {
+    let name = scan_name(scan)?;
     scan.expect(ProtoToken::Equals)?;
-    let Some(ProtoToken::IntLiteral(index)) = scan.next() else {
+    let index = scan_field_number(scan)?;
+    let mut options = vec![];
+    match scan.next_token() {
+        Some(ProtoToken::OpenBracket) => {}
+        Some(ProtoToken::OpenBrace) => {
+            options = scan_option_list(scan)?;
+            scan.expect(ProtoToken::OpenBracket)?;
+        }
+        Some(other) => bail!("wanted OpenBracket but got {other}"),
+        None => bail!("wanted OpenBracket but received EOF"),
+    }
+    let group = scan_message_body(scan, name.clone(), start, vec![])?;
+    let default = take_pseudo_option(&mut options, "default");
+    let json_name = take_pseudo_option(&mut options, "json_name");
+
+    Ok(MessageField {
+        label,
+        r#type: ProtoFieldType::Identifier(name.clone()),
+        name: name.to_lowercase(),
+        index,
+        default,
+        json_name,
+        options,
+        group: Some(group),
+        span: Span::new(start, scan.last_span().end()),
+        comments,
+    })
+}
+
+fn scan_field_number<T: Read>(scan: &mut Scanner<T>) -> Result<u32> {
+    let Some(ProtoToken::IntLiteral(index)) = scan.next_token() else {
         bail!("expected int literal")
     };
+    let Ok(index) = u32::try_from(index) else {
+        bail!("field number {index} is out of range")
+    };
+    Ok(index)
+}
+
+// scan_field_options scans the end of a field, which is either a `;` or bracketed options
+// followed by a `;`
+fn scan_field_options<T: Read>(scan: &mut Scanner<T>) -> Result<Vec<ProtoOption>> {
+    match scan.next_token() {
+        Some(ProtoToken::SemiColon) => return Ok(vec![]),
+        Some(ProtoToken::OpenBrace) => {}
+        Some(other) => bail!("wanted SemiColon but got {other}"),
+        None => bail!("wanted SemiColon but received EOF"),
+    }
+    let options = scan_option_list(scan)?;
     scan.expect(ProtoToken::SemiColon)?;
 
-    Ok(MessageField {
-        r#type,
-        name: String::from_utf8(name)?,
-        index: index.try_into()?,
+    Ok(options)
+}
+
+// scan_option_list scans comma separated options up to the closing `]`, after the opening `[`
+fn scan_option_list<T: Read>(scan: &mut Scanner<T>) -> Result<Vec<ProtoOption>> {
+    let mut options = vec![];
+    loop {
+        let (name, start) = scan_option_name(scan)?;
+        let Some(token) = scan.next_token() else {
+            bail!("expected an option value")
+        };
+        options.push(ProtoOption {
+            name,
+            value: scan_constant(scan, token)?,
+            span: Span::new(start, scan.last_span().end()),
+            comments: vec![],
+        });
+        match scan.next_token() {
+            Some(ProtoToken::Comma) => {}
+            Some(ProtoToken::CloseBrace) => break,
+            Some(other) => bail!("wanted CloseBrace but got {other}"),
+            None => bail!("wanted CloseBrace but received EOF"),
+        }
+    }
+
+    Ok(options)
+}
+
+// take_pseudo_option removes the first option called name, for options like `default` which are
+// really part of the field. Any later duplicates are left for validation to report.
+fn take_pseudo_option(options: &mut Vec<ProtoOption>, name: &str) -> Option<ProtoOption> {
+    let index = options.iter().position(|option| option.name() == name)?;
+    Some(options.remove(index))
+}
+
+// oneof = "oneof" oneofName "{" { option | oneofField } "}"
+fn scan_oneof<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoOneOf> {
+    let (start, comments) = node_start(scan);
+    let name = scan_name(scan)?;
+    scan.expect(ProtoToken::OpenBracket)?;
+
+    let mut fields = vec![];
+    let mut options = vec![];
+    while let Some(token) = scan.next_token() {
+        match token {
+            ProtoToken::CloseBracket => {
+                return Ok(ProtoOneOf {
+                    name,
+                    fields,
+                    options,
+                    span: Span::new(start, scan.last_span().end()),
+                    comments,
+                    trailing_comments: scan.leading_comments().to_vec(),
+                })
+            }
+            ProtoToken::SemiColon => {}
+            ProtoToken::Option => options.push(scan_option(scan)?),
+            // Labels aren't allowed here, but are kept for validation to report
+            token => fields.push(scan_labelled_field(scan, token)?),
+        }
+    }
+
+    bail!("expected CloseBracket but received EOF")
+}
+
+// enum = "enum" enumName "{" { option | enumField | reserved | emptyStatement } "}"
+fn scan_enum<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoEnum> {
+    let (start, comments) = node_start(scan);
+    let name = scan_name(scan)?;
+    scan.expect(ProtoToken::OpenBracket)?;
+
+    let mut enumeration = ProtoEnum {
+        name,
+        values: vec![],
+        options: vec![],
+        reserved_ranges: vec![],
+        reserved_names: vec![],
+        span: Span::default(),
+        comments,
+        trailing_comments: vec![],
+    };
+    while let Some(token) = scan.next_token() {
+        match token {
+            ProtoToken::CloseBracket => {
+                enumeration.span = Span::new(start, scan.last_span().end());
+                enumeration.trailing_comments = scan.leading_comments().to_vec();
+                return Ok(enumeration);
+            }
+            ProtoToken::SemiColon => {}
+            ProtoToken::Option => enumeration.options.push(scan_option(scan)?),
+            ProtoToken::Reserved => scan_reserved(
+                scan,
+                i32::MAX.into(),
+                &mut enumeration.reserved_ranges,
+                &mut enumeration.reserved_names,
+            )?,
+            token => {
+                let (start, comments) = node_start(scan);
+                let Some(name) = token.as_identifier() else {
+                    bail!("expected identifier")
+                };
+                scan.expect(ProtoToken::Equals)?;
+                let Some(token) = scan.next_token() else {
+                    bail!("expected int literal")
+                };
+                let number = scan_signed_int(scan, token)?;
+                let Ok(number) = i32::try_from(number) else {
+                    bail!(
+                        "enum value {name} is {number}, enum values must be between {} & {}",
+                        i32::MIN,
+                        i32::MAX
+                    )
+                };
+                let options = scan_field_options(scan)?;
+                enumeration.values.push(ProtoEnumValue {
+                    name,
+                    number,
+                    options,
+                    span: Span::new(start, scan.last_span().end()),
+                    comments,
+                });
+            }
+        }
+    }
+
+    bail!("expected CloseBracket but received EOF")
+}
+
+// scan_signed_int scans an int literal which may be preceded by a `-`
+fn scan_signed_int<T: Read>(scan: &mut Scanner<T>, first_token: ProtoToken) -> Result<i64> {
+    let (negative, i) = match first_token {
+        ProtoToken::IntLiteral(i) => (false, i),
+        ProtoToken::Minus => {
+            let Some(ProtoToken::IntLiteral(i)) = scan.next_token() else {
+                bail!("expected int literal")
+            };
+            (true, i)
+        }
+        other => bail!("expected int literal but got {other}"),
+    };
+    // Negated through i128 as i64::MIN's magnitude doesn't fit in an i64
+    let signed = if negative {
+        -i128::from(i)
+    } else {
+        i128::from(i)
+    };
+    match i64::try_from(signed) {
+        Ok(i) => Ok(i),
+        Err(_) if negative => bail!(
+            "integer -{i} is too small, it must be at least {}",
+            i64::MIN
+        ),
+        Err(_) => bail!("integer {i} is too large, it must be at most {}", i64::MAX),
+    }
+}
+
+// reserved = "reserved" ( ranges | strFieldNames ) ";"
+fn scan_reserved<T: Read>(
+    scan: &mut Scanner<T>,
+    max: i64,
+    ranges: &mut Vec<ProtoRange>,
+    names: &mut Vec<String>,
+) -> Result<()> {
+    let Some(token) = scan.next_token() else {
+        bail!("expected a reserved range or name")
+    };
+    if let ProtoToken::StringLiteral(_) = token {
+        let mut token = token;
+        loop {
+            let ProtoToken::StringLiteral(name) = token else {
+                bail!("expected string literal")
+            };
+            names.push(name);
+            match scan.next_token() {
+                Some(ProtoToken::Comma) => {}
+                Some(ProtoToken::SemiColon) => return Ok(()),
+                _ => bail!("expected Comma or SemiColon"),
+            }
+            let Some(next) = scan.next_token() else {
+                bail!("expected string literal")
+            };
+            token = next;
+        }
+    }
+
+    let (mut scanned, end) = scan_ranges(scan, token, max)?;
+    if end != ProtoToken::SemiColon {
+        bail!("wanted SemiColon but got {end}")
+    }
+    ranges.append(&mut scanned);
+    Ok(())
+}
+
+// ranges = range { "," range }
+// range =  intLit [ "to" ( intLit | "max" ) ]
+// scan_ranges returns the ranges along with the token which followed them
+fn scan_ranges<T: Read>(
+    scan: &mut Scanner<T>,
+    first_token: ProtoToken,
+    max: i64,
+) -> Result<(Vec<ProtoRange>, ProtoToken)> {
+    let mut ranges = vec![];
+    let mut token = first_token;
+    loop {
+        let range_start = scan.last_span().start();
+        let start = scan_signed_int(scan, token)?;
+        let mut range_end = scan.last_span().end();
+        let Some(mut next) = scan.next_token() else {
+            bail!("expected a range to be terminated")
+        };
+        let mut end = start;
+        if next == ProtoToken::To {
+            end = match scan.next_token() {
+                Some(ProtoToken::Max) => max,
+                Some(token) => scan_signed_int(scan, token)?,
+                None => bail!("expected the end of the range"),
+            };
+            range_end = scan.last_span().end();
+            let Some(token) = scan.next_token() else {
+                bail!("expected a range to be terminated")
+            };
+            next = token;
+        }
+        ranges.push(ProtoRange {
+            start,
+            end,
+            options: vec![],
+            span: Span::new(range_start, range_end),
+        });
+
+        if next != ProtoToken::Comma {
+            return Ok((ranges, next));
+        }
+        let Some(next) = scan.next_token() else {
+            bail!("expected a range")
+        };
+        token = next;
+    }
+}
+
+// extend = "extend" messageType "{" {field | emptyStatement} "}"
+fn scan_extend<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoExtend> {
+    let (start, comments) = node_start(scan);
+    let Some(token) = scan.next_token() else {
+        bail!("expected a message type")
+    };
+    let extendee = ProtoFieldType::from_token(token, scan)?;
+    scan.expect(ProtoToken::OpenBracket)?;
+
+    let mut fields = vec![];
+    while let Some(token) = scan.next_token() {
+        match token {
+            ProtoToken::CloseBracket => {
+                return Ok(ProtoExtend {
+                    extendee,
+                    fields,
+                    span: Span::new(start, scan.last_span().end()),
+                    comments,
+                    trailing_comments: scan.leading_comments().to_vec(),
+                })
+            }
+            ProtoToken::SemiColon => {}
+            token => fields.push(scan_labelled_field(scan, token)?),
+        }
+    }
+
+    bail!("expected CloseBracket but received EOF")
+}
+
+// service = "service" serviceName "{" { option | rpc | emptyStatement } "}"
+fn scan_service<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoService> {
+    let (start, comments) = node_start(scan);
+    let name = scan_name(scan)?;
+    scan.expect(ProtoToken::OpenBracket)?;
+
+    let mut rpcs = vec![];
+    let mut options = vec![];
+    while let Some(token) = scan.next_token() {
+        match token {
+            ProtoToken::CloseBracket => {
+                return Ok(ProtoService {
+                    name,
+                    rpcs,
+                    options,
+                    span: Span::new(start, scan.last_span().end()),
+                    comments,
+                    trailing_comments: scan.leading_comments().to_vec(),
+                })
+            }
+            ProtoToken::SemiColon => {}
+            ProtoToken::Option => options.push(scan_option(scan)?),
+            ProtoToken::Rpc => rpcs.push(scan_rpc(scan)?),
+            other => bail!("unexpected token {other}"),
+        }
+    }
+
+    bail!("expected CloseBracket but received EOF")
+}
+
+// rpc = "rpc" rpcName "(" [ "stream" ] messageType ")" "returns" "(" [ "stream" ]
+//       messageType ")" (( "{" {option | emptyStatement } "}" ) | ";")
+fn scan_rpc<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoRpc> {
+    let (start, comments) = node_start(scan);
+    let name = scan_name(scan)?;
+    let request = scan_rpc_type(scan)?;
+    scan.expect(ProtoToken::Returns)?;
+    let returns = scan_rpc_type(scan)?;
+
+    let mut options = vec![];
+    match scan.next_token() {
+        Some(ProtoToken::SemiColon) => {}
+        Some(ProtoToken::OpenBracket) => loop {
+            match scan.next_token() {
+                Some(ProtoToken::CloseBracket) => break,
+                Some(ProtoToken::SemiColon) => {}
+                Some(ProtoToken::Option) => options.push(scan_option(scan)?),
+                Some(other) => bail!("unexpected token {other}"),
+                None => bail!("expected CloseBracket but received EOF"),
+            }
+        },
+        Some(other) => bail!("wanted SemiColon but got {other}"),
+        None => bail!("wanted SemiColon but received EOF"),
+    }
+
+    Ok(ProtoRpc {
+        name,
+        request,
+        returns,
+        options,
+        span: Span::new(start, scan.last_span().end()),
+        comments,
     })
 }
 
-fn scan_service<T: Read>(_scan: &mut Scanner<T>) -> Result<ProtoService> {
-    todo!()
+fn scan_rpc_type<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoRpcType> {
+    scan.expect(ProtoToken::OpenParen)?;
+    let Some(mut token) = scan.next_token() else {
+        bail!("expected a message type")
+    };
+    let stream = token == ProtoToken::Stream;
+    if stream {
+        let Some(next) = scan.next_token() else {
+            bail!("expected a message type")
+        };
+        // A message called stream, `rpc Get(stream) returns (Foo)`
+        if next == ProtoToken::CloseParen {
+            return Ok(ProtoRpcType {
+                stream: false,
+                r#type: ProtoFieldType::Identifier("stream".to_owned()),
+            });
+        }
+        token = next;
+    }
+    let r#type = ProtoFieldType::from_token(token, scan)?;
+    scan.expect(ProtoToken::CloseParen)?;
+
+    Ok(ProtoRpcType { stream, r#type })
 }
 
+// syntax = "syntax" "=" ("'proto2'" | '"proto2"' | "'proto3'" | '"proto3"') ";"
 fn scan_syntax<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoSyntax> {
-    scan.expect(ProtoToken::Syntax)?;
     scan.expect(ProtoToken::Equals)?;
-    let Some(ProtoToken::StringLiteral(syntax)) = scan.next() else {
+    let Some(ProtoToken::StringLiteral(syntax)) = scan.next_token() else {
         bail!("expected string literal")
     };
     let s = match syntax.as_str() {
@@ -241,7 +853,8 @@ fn scan_syntax<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoSyntax> {
 }
 
 fn scan_import<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoImport> {
-    let Some(mut next) = scan.next() else {
+    let (start, comments) = node_start(scan);
+    let Some(mut next) = scan.next_token() else {
         bail!("expected either 'public', 'weak' or a string literal after 'import'")
     };
     let r#type = match next {
@@ -250,24 +863,28 @@ fn scan_import<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoImport> {
         _ => ProtoImportType::Default,
     };
     if r#type != ProtoImportType::Default {
-        let Some(token) = scan.next() else {
+        let Some(token) = scan.next_token() else {
             bail!("expected a string literal to import")
         };
         next = token;
     }
-    let ProtoToken::StringLiteral(import) = next else{
+    let ProtoToken::StringLiteral(import) = next else {
         bail!("expected string literal")
     };
     scan.expect(ProtoToken::SemiColon)?;
     Ok(ProtoImport {
         r#type,
         path: import,
+        span: Span::new(start, scan.last_span().end()),
+        comments,
     })
 }
 
 fn scan_package<T: Read>(scan: &mut Scanner<T>) -> Result<Vec<Vec<u8>>> {
-    let Some(ProtoToken::FullIdentifier(pkg)) = scan.next() else {
-        bail!("expected identifier")
+    let pkg = match scan.next_token() {
+        Some(ProtoToken::FullIdentifier(pkg)) => pkg,
+        Some(ProtoToken::Identifier(pkg)) => vec![pkg],
+        _ => bail!("expected identifier"),
     };
     scan.expect(ProtoToken::SemiColon)?;
 
@@ -275,21 +892,160 @@ fn scan_package<T: Read>(scan: &mut Scanner<T>) -> Result<Vec<Vec<u8>>> {
 }
 
 fn scan_option<T: Read>(scan: &mut Scanner<T>) -> Result<ProtoOption> {
-    let Some(ProtoToken::Identifier(id)) = scan.next() else {
-        bail!("expected identifier")
-    };
-    scan.expect(ProtoToken::Equals)?;
-    let Some(ProtoToken::StringLiteral(opt)) = scan.next() else {
-        bail!("expected string literal")
+    let (start, comments) = node_start(scan);
+    let (name, _) = scan_option_name(scan)?;
+    let Some(token) = scan.next_token() else {
+        bail!("expected an option value")
     };
+    let value = scan_constant(scan, token)?;
     scan.expect(ProtoToken::SemiColon)?;
 
     Ok(ProtoOption {
-        name: String::from_utf8(id)?,
-        value: opt,
+        name,
+        value,
+        span: Span::new(start, scan.last_span().end()),
+        comments,
+    })
+}
+
+// optionName = ( ident | "(" ["."] fullIdent ")" ) { "." ( ident | "(" ["."] fullIdent ")" ) }
+// scan_option_name scans the name up to & including the following `=`, returning it along with
+// where it starts
+fn scan_option_name<T: Read>(scan: &mut Scanner<T>) -> Result<(String, Position)> {
+    let mut name = String::new();
+    let mut start = None;
+    loop {
+        let token = scan.next_token();
+        let start = *start.get_or_insert(scan.last_span().start());
+        match token {
+            Some(ProtoToken::Equals) if !name.is_empty() => return Ok((name, start)),
+            Some(ProtoToken::OpenParen) => name.push('('),
+            Some(ProtoToken::CloseParen) => name.push(')'),
+            Some(ProtoToken::Dot) => name.push('.'),
+            Some(ProtoToken::FullIdentifier(id)) => name.push_str(&join_identifier(&id)?),
+            Some(token) => match token.as_identifier() {
+                Some(id) => name.push_str(&id),
+                None => bail!("unexpected token {token} in option name"),
+            },
+            None => bail!("expected an option name but received EOF"),
+        }
+    }
+}
+
+fn join_identifier(id: &[Vec<u8>]) -> Result<String> {
+    Ok(id
+        .iter()
+        .map(|part| String::from_utf8(part.clone()))
+        .collect::<Result<Vec<_>, _>>()?
+        .join("."))
+}
+
+// constant = fullIdent | ( [ "-" | "+" ] intLit ) | ( [ "-" | "+" ] floatLit ) | strLit | boolLit
+//            | "{" messageLiteral "}"
+fn scan_constant<T: Read>(scan: &mut Scanner<T>, first_token: ProtoToken) -> Result<ProtoConstant> {
+    Ok(match first_token {
+        ProtoToken::StringLiteral(s) => ProtoConstant::String(s),
+        ProtoToken::BytesLiteral(b) => ProtoConstant::Bytes(b),
+        ProtoToken::IntLiteral(i) => ProtoConstant::Int(i.into()),
+        ProtoToken::FloatLiteral(f) => ProtoConstant::Float(f),
+        ProtoToken::Inf => ProtoConstant::Float(f64::INFINITY),
+        ProtoToken::Plus => match scan.next_token() {
+            Some(token @ (ProtoToken::IntLiteral(_) | ProtoToken::FloatLiteral(_))) => {
+                scan_constant(scan, token)?
+            }
+            _ => bail!("expected a number after '+'"),
+        },
+        ProtoToken::Minus => match scan.next_token() {
+            Some(ProtoToken::IntLiteral(i)) => ProtoConstant::Int(-i128::from(i)),
+            Some(ProtoToken::FloatLiteral(f)) => ProtoConstant::Float(-f),
+            Some(ProtoToken::Inf) => ProtoConstant::Float(f64::NEG_INFINITY),
+            Some(ProtoToken::Identifier(id)) if id == b"nan" => ProtoConstant::Float(-f64::NAN),
+            _ => bail!("expected a number after '-'"),
+        },
+        ProtoToken::FullIdentifier(id) => ProtoConstant::Identifier(join_identifier(&id)?),
+        ProtoToken::OpenBracket => ProtoConstant::Aggregate(scan_aggregate(scan)?),
+        token => match token.as_identifier().as_deref() {
+            Some("true") => ProtoConstant::Bool(true),
+            Some("false") => ProtoConstant::Bool(false),
+            Some("nan") => ProtoConstant::Float(f64::NAN),
+            Some(id) => ProtoConstant::Identifier(id.to_owned()),
+            None => bail!("expected a constant but got {token}"),
+        },
     })
 }
 
+// scan_aggregate scans the fields of a text format message up to & including the closing `}`
+fn scan_aggregate<T: Read>(scan: &mut Scanner<T>) -> Result<Vec<ProtoAggregateField>> {
+    let mut fields = vec![];
+    let mut next = scan.next_token();
+    loop {
+        let name = match next {
+            Some(ProtoToken::CloseBracket) => return Ok(fields),
+            // Extension fields, `[foo.bar]: 1`
+            Some(ProtoToken::OpenBrace) => {
+                let mut name = "[".to_owned();
+                loop {
+                    match scan.next_token() {
+                        Some(ProtoToken::CloseBrace) => break,
+                        Some(ProtoToken::Dot) => name.push('.'),
+                        Some(ProtoToken::FullIdentifier(id)) => {
+                            name.push_str(&join_identifier(&id)?)
+                        }
+                        Some(token) => match token.as_identifier() {
+                            Some(id) => name.push_str(&id),
+                            None => bail!("unexpected token {token} in extension name"),
+                        },
+                        None => bail!("expected CloseBrace but received EOF"),
+                    }
+                }
+                name.push(']');
+                name
+            }
+            Some(token) => match token.as_identifier() {
+                Some(name) => name,
+                None => bail!("expected a field name but got {token}"),
+            },
+            None => bail!("expected CloseBracket but received EOF"),
+        };
+
+        // The colon is optional before a message value
+        let value = match scan.next_token() {
+            Some(ProtoToken::Colon) => match scan.next_token() {
+                Some(ProtoToken::OpenBrace) => ProtoConstant::List(scan_list(scan)?),
+                Some(token) => scan_constant(scan, token)?,
+                None => bail!("expected a value but received EOF"),
+            },
+            Some(ProtoToken::OpenBracket) => ProtoConstant::Aggregate(scan_aggregate(scan)?),
+            Some(other) => bail!("wanted Colon but got {other}"),
+            None => bail!("wanted Colon but received EOF"),
+        };
+        fields.push(ProtoAggregateField { name, value });
+
+        next = scan.next_token();
+        if matches!(next, Some(ProtoToken::Comma | ProtoToken::SemiColon)) {
+            next = scan.next_token();
+        }
+    }
+}
+
+// scan_list scans the values of a list within a text format message up to & including the `]`
+fn scan_list<T: Read>(scan: &mut Scanner<T>) -> Result<Vec<ProtoConstant>> {
+    let mut values = vec![];
+    loop {
+        match scan.next_token() {
+            Some(ProtoToken::CloseBrace) if values.is_empty() => return Ok(values),
+            Some(token) => values.push(scan_constant(scan, token)?),
+            None => bail!("expected CloseBrace but received EOF"),
+        }
+        match scan.next_token() {
+            Some(ProtoToken::Comma) => {}
+            Some(ProtoToken::CloseBrace) => return Ok(values),
+            Some(other) => bail!("wanted CloseBrace but got {other}"),
+            None => bail!("wanted CloseBrace but received EOF"),
+        }
+    }
+}
+
 fn read_n<R: Read>(reader: &mut R, bytes_to_read: u64) -> Result<Vec<u8>> {
     let mut buf = vec![];
     let mut chunk = reader.take(bytes_to_read);
@@ -299,3 +1055,434 @@ fn read_n<R: Read>(reader: &mut R, bytes_to_read: u64) -> Result<Vec<u8>> {
     }
     Ok(buf)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_basic_file() {
+        let f = parse(
+            r#"syntax = "proto3";
+
+package foo.bar.v1;
+
+import "foo/bar/baz.proto";
+
+message TestMessage {
+    string name = 1;
+    foo.bar.baz.Amount amount = 2;
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(&ProtoSyntax::Proto3, f.syntax());
+        assert_eq!(["foo", "bar", "v1"], f.package());
+        assert_eq!("foo/bar/baz.proto", f.imports()[0].path());
+        let fields = f.messages()[0].fields();
+        assert_eq!("amount", fields[1].name());
+        assert_eq!(2, fields[1].index());
+        assert_eq!(
+            &ProtoFieldType::FullIdentifier(vec![
+                "foo".into(),
+                "bar".into(),
+                "baz".into(),
+                "Amount".into()
+            ]),
+            fields[1].field_type()
+        );
+    }
+
+    #[test]
+    fn parse_full_grammar() {
+        let f = parse(
+            r#"syntax = "proto2";
+package foo;
+import public "other.proto";
+option (custom.http) = { get: "/v1/foo" [ext.name]: 1, tags: ["a", "b"] nested { x: -2 } };
+
+message Outer {
+    option deprecated = true;
+    reserved 2, 15 to max;
+    reserved "old";
+    extensions 100 to 200;
+    extensions 300, 400 to 500 [verification = UNVERIFIED];
+    optional int32 a = 1 [default = -5, (validate.rules).int32.gt = 0];
+    repeated Inner inner = 3;
+    message Inner {}
+    enum Kind {
+        KIND_UNKNOWN = 0;
+        KIND_OTHER = -1 [deprecated = true];
+        reserved 5 to max;
+    }
+    oneof choice {
+        string s = 4;
+        bytes b = 5;
+    }
+    extend Other {
+        optional uint64 id = 101;
+    }
+}
+
+service Svc {
+    rpc Get(stream Outer) returns (Outer.Inner);
+    rpc List(Outer) returns (stream Outer) { option idempotency_level = NO_SIDE_EFFECTS; }
+}"#,
+        )
+        .unwrap();
+
+        assert_eq!(&ProtoSyntax::Proto2, f.syntax());
+        assert_eq!(["foo"], f.package());
+        assert_eq!(&ProtoImportType::Public, f.imports()[0].import_type());
+        assert_eq!("(custom.http)", f.options()[0].name());
+        assert_eq!(
+            &ProtoConstant::Aggregate(vec![
+                ProtoAggregateField {
+                    name: "get".into(),
+                    value: ProtoConstant::String("/v1/foo".into()),
+                },
+                ProtoAggregateField {
+                    name: "[ext.name]".into(),
+                    value: ProtoConstant::Int(1),
+                },
+                ProtoAggregateField {
+                    name: "tags".into(),
+                    value: ProtoConstant::List(vec![
+                        ProtoConstant::String("a".into()),
+                        ProtoConstant::String("b".into()),
+                    ]),
+                },
+                ProtoAggregateField {
+                    name: "nested".into(),
+                    value: ProtoConstant::Aggregate(vec![ProtoAggregateField {
+                        name: "x".into(),
+                        value: ProtoConstant::Int(-2),
+                    }]),
+                },
+            ]),
+            f.options()[0].value()
+        );
+
+        let outer = &f.messages()[0];
+        let bounds = |ranges: &[ProtoRange]| -> Vec<(i64, i64)> {
+            ranges.iter().map(|r| (r.start(), r.end())).collect()
+        };
+        assert_eq!(
+            vec![(2, 2), (15, FIELD_NUMBER_MAX)],
+            bounds(outer.reserved_ranges())
+        );
+        assert_eq!(["old"], outer.reserved_names());
+        assert_eq!(
+            vec![(100, 200), (300, 300), (400, 500)],
+            bounds(outer.extension_ranges())
+        );
+        assert!(outer.extension_ranges()[0].options().is_empty());
+        for range in &outer.extension_ranges()[1..] {
+            assert_eq!(
+                vec!["verification"],
+                range
+                    .options()
+                    .iter()
+                    .map(ProtoOption::name)
+                    .collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(Some(ProtoFieldLabel::Optional), outer.fields()[0].label());
+        assert_eq!(
+            vec!["(validate.rules).int32.gt"],
+            outer.fields()[0]
+                .options()
+                .iter()
+                .map(ProtoOption::name)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some(&ProtoConstant::Int(-5)),
+            outer.fields()[0].default().map(ProtoOption::value)
+        );
+        assert_eq!("Inner", outer.messages()[0].name());
+        let kind = &outer.enums()[0];
+        assert_eq!(-1, kind.values()[1].number());
+        assert_eq!(i64::from(i32::MAX), kind.reserved_ranges()[0].end());
+        assert_eq!(2, outer.oneofs()[0].fields().len());
+        assert_eq!(
+            &ProtoFieldType::Identifier("Other".into()),
+            outer.extends()[0].extendee()
+        );
+        assert_eq!(101, outer.extends()[0].fields()[0].index());
+
+        let rpcs = f.services()[0].rpcs();
+        assert!(rpcs[0].request().is_stream());
+        assert!(!rpcs[0].returns().is_stream());
+        assert!(rpcs[1].returns().is_stream());
+        assert_eq!(
+            &ProtoConstant::Identifier("NO_SIDE_EFFECTS".into()),
+            rpcs[1].options()[0].value()
+        );
+    }
+
+    #[test]
+    fn parse_spans_and_comments() {
+        let source = r#"syntax = "proto3";
+// Leading
+// comments
+message Foo {
+    string a = 1; // before b
+    /* Documents Bar */ enum Bar { BAR_UNKNOWN = 0; }
+}"#;
+        let f = parse(source).unwrap();
+        let text = |span: Span| &source[span.start().offset()..span.end().offset()];
+
+        let message = &f.messages()[0];
+        assert_eq!([" Leading", " comments"], message.comments());
+        assert!(text(message.span()).starts_with("message Foo {"));
+        assert!(text(message.span()).ends_with('}'));
+        assert_eq!(3, message.span().start().line());
+
+        let field = &message.fields()[0];
+        assert!(field.comments().is_empty());
+        assert_eq!("string a = 1;", text(field.span()));
+
+        // Comments on the end of a line belong to whatever follows them
+        let enumeration = &message.enums()[0];
+        assert_eq!([" before b", " Documents Bar "], enumeration.comments());
+        assert_eq!("BAR_UNKNOWN = 0;", text(enumeration.values()[0].span()));
+    }
+
+    fn messages(parsed: &ParsedFile) -> Vec<String> {
+        parsed
+            .diagnostics()
+            .iter()
+            .map(ToString::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn parse_file_defaults_to_proto2() {
+        let parsed = parse_file("foo/bar.proto", "package foo;\nmessage Foo {}");
+
+        assert_eq!("foo/bar.proto", parsed.name());
+        assert!(!parsed.has_errors());
+        assert_eq!(&ProtoSyntax::Proto2, parsed.file().unwrap().syntax());
+        assert_eq!(
+            vec!["1:1: warning: no syntax specified, defaulting to proto2"],
+            messages(&parsed)
+        );
+    }
+
+    #[test]
+    fn parse_file_misplaced_syntax() {
+        let parsed = parse_file(
+            "foo.proto",
+            "package foo;\nsyntax = \"proto3\";\nsyntax = \"proto2\";",
+        );
+
+        assert_eq!(
+            vec![
+                "2:1: error: syntax must be the first statement in the file",
+                "3:1: error: duplicate syntax statement",
+            ],
+            messages(&parsed)
+        );
+        assert_eq!(
+            Some(&ProtoSyntax::Proto3),
+            parsed.file().map(ProtoFile::syntax)
+        );
+    }
+
+    #[test]
+    fn parse_file_duplicate_package() {
+        let parsed = parse_file(
+            "foo.proto",
+            "syntax = \"proto3\";\nmessage Foo {}\npackage foo;\npackage bar;",
+        );
+
+        assert!(parsed.has_errors());
+        assert_eq!(
+            vec![
+                "3:1: warning: package should be declared before any definitions",
+                "4:1: error: duplicate package statement, first declared at 3:1",
+            ],
+            messages(&parsed)
+        );
+        assert_eq!(["foo"], parsed.file().unwrap().package());
+        assert_eq!(
+            "4:1: error: duplicate package statement, first declared at 3:1",
+            parse("syntax = \"proto3\";\nmessage Foo {}\npackage foo;\npackage bar;")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn parse_absolute_types() {
+        let file = parse(
+            r#"syntax = "proto3";
+message Foo {
+    .Bar a = 1;
+    .foo.Bar b = 2;
+}
+service Svc { rpc Get(.foo.Bar) returns (.Bar); }
+extend .google.protobuf.FieldOptions { string c = 1000; }"#,
+        )
+        .unwrap();
+
+        let absolute = |parts: &[&str]| {
+            ProtoFieldType::FullIdentifier(parts.iter().map(|part| part.to_string()).collect())
+        };
+        let fields = file.messages()[0].fields();
+        assert_eq!(&absolute(&["", "Bar"]), fields[0].field_type());
+        assert_eq!(&absolute(&["", "foo", "Bar"]), fields[1].field_type());
+        assert_eq!(".foo.Bar", fields[1].field_type().name());
+
+        let rpc = &file.services()[0].rpcs()[0];
+        assert_eq!(&absolute(&["", "foo", "Bar"]), rpc.request().message_type());
+        assert_eq!(&absolute(&["", "Bar"]), rpc.returns().message_type());
+        assert_eq!(
+            ".google.protobuf.FieldOptions",
+            file.extends()[0].extendee().name()
+        );
+    }
+
+    #[test]
+    fn parse_groups() {
+        let file = parse(
+            r#"syntax = "proto2";
+message Foo {
+    repeated group Result = 1 [deprecated = true] {
+        optional string url = 2;
+    }
+    oneof choice { group Choice = 3 {} }
+}
+extend Foo { optional group Ext = 100 {} }"#,
+        )
+        .unwrap();
+
+        let message = &file.messages()[0];
+        let field = &message.fields()[0];
+        assert_eq!("result", field.name());
+        assert_eq!(1, field.index());
+        assert_eq!(Some(ProtoFieldLabel::Repeated), field.label());
+        assert_eq!(
+            &ProtoFieldType::Identifier("Result".into()),
+            field.field_type()
+        );
+        assert_eq!("deprecated", field.options()[0].name());
+        let group = field.group().unwrap();
+        assert_eq!("Result", group.name());
+        assert_eq!("url", group.fields()[0].name());
+        assert_eq!("choice", message.oneofs()[0].fields()[0].name());
+        assert_eq!("ext", file.extends()[0].fields()[0].name());
+    }
+
+    #[test]
+    fn parse_adjacent_strings() {
+        let file =
+            parse("syntax = \"proto3\";\noption java_package = \"com.\" 'foo'\n    \".bar\";")
+                .unwrap();
+
+        assert_eq!(
+            &ProtoConstant::String("com.foo.bar".into()),
+            file.options()[0].value()
+        );
+        assert_eq!(2, file.options()[0].span().end().line());
+    }
+
+    #[test]
+    fn parse_keyword_types() {
+        let file = parse(
+            r#"syntax = "proto3";
+message Foo {
+    stream stream = 1;
+    returns returns = 2;
+}
+service Svc {
+    rpc Get(stream) returns (stream stream);
+}"#,
+        )
+        .unwrap();
+
+        let fields = file.messages()[0].fields();
+        assert_eq!(
+            &ProtoFieldType::Identifier("stream".into()),
+            fields[0].field_type()
+        );
+        assert_eq!("stream", fields[0].name());
+        assert_eq!(
+            &ProtoFieldType::Identifier("returns".into()),
+            fields[1].field_type()
+        );
+
+        let rpc = &file.services()[0].rpcs()[0];
+        assert!(!rpc.request().is_stream());
+        assert_eq!("stream", rpc.request().message_type().name());
+        assert!(rpc.returns().is_stream());
+        assert_eq!("stream", rpc.returns().message_type().name());
+    }
+
+    #[test]
+    fn parse_labelled_oneof_fields() {
+        let file = parse(
+            "syntax = \"proto2\";\nmessage Foo {\n    oneof o { optional int32 a = 1; required int32 b = 2; }\n}",
+        )
+        .unwrap();
+
+        let fields = file.messages()[0].oneofs()[0].fields();
+        assert_eq!(Some(ProtoFieldLabel::Optional), fields[0].label());
+        assert_eq!(Some(ProtoFieldLabel::Required), fields[1].label());
+    }
+
+    #[test]
+    fn parse_enum_value_out_of_range() {
+        let err =
+            parse("syntax = \"proto3\";\nenum Kind {\n    KIND_BIG = 2147483648;\n}").unwrap_err();
+
+        assert_eq!(
+            "3:16: error: enum value KIND_BIG is 2147483648, enum values must be between \
+             -2147483648 & 2147483647",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn parse_signed_int_bounds() {
+        let err =
+            parse("syntax = \"proto3\";\nenum Kind {\n    KIND_MIN = -9223372036854775808;\n}")
+                .unwrap_err();
+        assert_eq!(
+            "3:17: error: enum value KIND_MIN is -9223372036854775808, enum values must be \
+             between -2147483648 & 2147483647",
+            err.to_string()
+        );
+
+        let err =
+            parse("syntax = \"proto3\";\nenum Kind {\n    KIND_MIN = -9223372036854775809;\n}")
+                .unwrap_err();
+        assert_eq!(
+            "3:17: error: integer -9223372036854775809 is too small, it must be at least \
+             -9223372036854775808",
+            err.to_string()
+        );
+
+        let file = parse(
+            "syntax = \"proto2\";\nmessage Foo {\n    optional int64 a = 1 [default = -9223372036854775808];\n}",
+        )
+        .unwrap();
+        assert_eq!(
+            Some(Ok(crate::ast::ProtoDefault::Int(i64::MIN))),
+            file.messages()[0].fields()[0].default_value()
+        );
+    }
+
+    #[test]
+    fn parse_error_position() {
+        let err = parse("syntax = \"proto3\";\npackage foo.bar\nmessage Foo {}").unwrap_err();
+
+        assert_eq!(2, err.span().start().line());
+        assert_eq!(0, err.span().start().column());
+        assert_eq!(
+            "3:1: error: wanted SemiColon but got Message",
+            err.to_string()
+        );
+    }
+}
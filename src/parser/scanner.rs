@@ -9,11 +9,16 @@ use anyhow::{bail, Result};
 use crate::parser::read_n;
 
 use crate::parser::tokens::ProtoToken;
+use crate::span::{Position, Span};
 
 pub struct Scanner<T: Read> {
     reader: BufReader<T>,
     done: bool,
     buffer: RefCell<Vec<u8>>,
+    position: Position,
+    token_start: Position,
+    pending_comments: Vec<String>,
+    leading_comments: Vec<String>,
 }
 
 impl<T: Read> Scanner<T> {
@@ -22,9 +27,23 @@ impl<T: Read> Scanner<T> {
             reader: BufReader::new(reader),
             done: false,
             buffer: RefCell::new(Vec::with_capacity(8)),
+            position: Position::default(),
+            token_start: Position::default(),
+            pending_comments: vec![],
+            leading_comments: vec![],
         }
     }
 
+    /// The position of the next unread character
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// The span of the token most recently returned by `next`
+    pub fn last_span(&self) -> Span {
+        Span::new(self.token_start, self.position)
+    }
+
     fn peek(&mut self) -> Option<u8> {
         if self.is_buffer_empty() {
             let _ = self.load_buffer(1);
@@ -41,14 +60,26 @@ impl<T: Read> Scanner<T> {
         self.append_buffer(len)
     }
 
+    // peek_at looks n characters ahead without consuming anything, peek_at(0) is the same as peek
+    fn peek_at(&mut self, n: usize) -> Option<u8> {
+        while self.buffer.borrow().len() <= n {
+            if !matches!(self.append_buffer(1), Ok(1)) {
+                return None;
+            }
+        }
+
+        self.buffer.borrow().get(n).copied()
+    }
+
     fn is_buffer_empty(&self) -> bool {
         self.buffer.borrow().is_empty()
     }
 
     fn append_buffer(&mut self, len: u64) -> Result<usize> {
         let mut bytes = read_n(&mut self.reader, len)?;
+        let read = bytes.len();
         self.buffer.borrow_mut().append(&mut bytes);
-        Ok(bytes.len())
+        Ok(read)
     }
 
     fn is_done(&self) -> bool {
@@ -56,16 +87,11 @@ impl<T: Read> Scanner<T> {
     }
 
     fn pop(&mut self) -> Option<()> {
-        if self.is_buffer_empty() {
-            // TODO: We can avoid the extra buffer logic without using peek
-            let b = self.peek();
-            self.buffer.borrow_mut().clear();
-            b.map(|_| ())
-        } else {
-            let mut buf = self.buffer.borrow_mut();
-            buf.drain(0..1);
-            Some(())
-        }
+        // TODO: We can avoid the extra buffer logic without using peek
+        let b = self.peek()?;
+        self.buffer.borrow_mut().drain(0..1);
+        self.position.advance(b);
+        Some(())
     }
 
     fn scan(&mut self, predicate: impl Fn(&u8) -> bool) -> Option<Vec<u8>> {
@@ -91,6 +117,7 @@ impl<T: Read> Scanner<T> {
             match c {
                 b'a'..=b'z' => {}
                 b'A'..=b'Z' => {}
+                b'_' => {}
                 _ => return None,
             }
         }
@@ -139,17 +166,78 @@ impl<T: Read> Scanner<T> {
         }
     }
 
-    fn int_literal(&mut self) -> Option<Vec<u8>> {
-        if let Some(c) = self.peek() {
-            match c {
-                b'1'..=b'9' => {}
-                _ => return None,
+    // number scans an int or float literal, ints with a `0x` prefix are hex & a leading `0` is octal
+    fn number(&mut self) -> Option<ProtoToken> {
+        let first = self.peek()?;
+        let leading_dot = first == b'.' && self.peek_at(1).is_some_and(|c| c.is_ascii_digit());
+        if !first.is_ascii_digit() && !leading_dot {
+            return None;
+        }
+
+        if first == b'0' && matches!(self.peek_at(1), Some(b'x' | b'X')) {
+            self.pop();
+            self.pop();
+            let digits = self.scan(u8::is_ascii_hexdigit).unwrap_or_default();
+            return Some(int_token(&digits, 16));
+        }
+
+        let mut digits = self.scan(u8::is_ascii_digit).unwrap_or_default();
+        let mut float = false;
+        if self.peek() == Some(b'.') {
+            float = true;
+            self.pop();
+            digits.push(b'.');
+            digits.append(&mut self.scan(u8::is_ascii_digit).unwrap_or_default());
+        }
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            float = true;
+            self.pop();
+            digits.push(b'e');
+            if let Some(sign @ (b'+' | b'-')) = self.peek() {
+                self.pop();
+                digits.push(sign);
             }
+            digits.append(&mut self.scan(u8::is_ascii_digit).unwrap_or_default());
+        }
+
+        if float {
+            return Some(float_token(&digits));
+        }
+        if digits.len() > 1 && digits[0] == b'0' {
+            return Some(int_token(&digits[1..], 8));
         }
-        self.scan(|c| matches!(c, b'0'..=b'9'))
+        Some(match int_token(&digits, 10) {
+            // Decimals too large for a u64 are still valid as floats
+            ProtoToken::Invalid => float_token(&digits),
+            token => token,
+        })
     }
 
+    // string scans a string literal, along with any following it after whitespace as adjacent
+    // strings are concatenated, `"a" "b"` is `"ab"`
     fn string(&mut self) -> Option<Result<Vec<u8>>> {
+        let mut value = self.single_string()?;
+        while value.is_ok() && self.string_follows() {
+            self.whitespace();
+            let part = self.single_string()?;
+            value = value.and_then(|mut value| {
+                value.append(&mut part?);
+                Ok(value)
+            });
+        }
+        Some(value)
+    }
+
+    // string_follows looks past any whitespace for the start of another string literal
+    fn string_follows(&mut self) -> bool {
+        let mut n = 0;
+        while matches!(self.peek_at(n), Some(b' ' | b'\r' | b'\n' | b'\t')) {
+            n += 1;
+        }
+        matches!(self.peek_at(n), Some(b'"' | b'\''))
+    }
+
+    fn single_string(&mut self) -> Option<Result<Vec<u8>>> {
         let open_char = match self.peek() {
             Some(b'"') => b'"',
             Some(b'\'') => b'\'',
@@ -172,13 +260,43 @@ impl<T: Read> Scanner<T> {
                 bail!("expected a character after an escape character")
             };
             self.pop();
-            buffer.push(b);
+            buffer.push(self.escape(b));
             buffer.append(&mut self.take_until_consume(|c| c != open_char && c != &b'\\', false)?);
         }
         self.pop();
         Ok(buffer)
     }
 
+    // escape returns the byte an escape sequence represents, `c` is the character after the `\`
+    // & any further characters of hex or octal escapes are consumed
+    fn escape(&mut self, c: u8) -> u8 {
+        match c {
+            b'n' => b'\n',
+            b't' => b'\t',
+            b'r' => b'\r',
+            b'a' => 0x07,
+            b'b' => 0x08,
+            b'f' => 0x0c,
+            b'v' => 0x0b,
+            b'x' | b'X' => self.escape_digits(16, 2, 0),
+            b'0'..=b'7' => self.escape_digits(8, 2, c - b'0'),
+            // Quotes, `\`, `?` & anything unknown are taken literally
+            c => c,
+        }
+    }
+
+    // escape_digits consumes up to max_len more digits of radix, adding them onto value
+    fn escape_digits(&mut self, radix: u32, max_len: usize, mut value: u8) -> u8 {
+        for _ in 0..max_len {
+            let Some(digit) = self.peek().and_then(|c| (c as char).to_digit(radix)) else {
+                break;
+            };
+            self.pop();
+            value = value.wrapping_mul(radix as u8).wrapping_add(digit as u8);
+        }
+        value
+    }
+
     // whitespace consumes all of the whitespace characters
     fn whitespace(&mut self) {
         let _ = self.take_until(|c| matches!(c, b' ' | b'\r' | b'\n' | b'\t'), true);
@@ -239,14 +357,31 @@ impl<T: Read> Scanner<T> {
         bail!("didn't match predicate")
     }
 
+    /// The comments directly before the token most recently returned by `next_token`
+    pub fn leading_comments(&self) -> &[String] {
+        &self.leading_comments
+    }
+
+    /// next_token returns the next token which isn't a comment, keeping the comments skipped
+    /// over as its leading comments
+    pub fn next_token(&mut self) -> Option<ProtoToken> {
+        loop {
+            match self.next()? {
+                ProtoToken::Comment(comment) => self
+                    .pending_comments
+                    .push(String::from_utf8_lossy(&comment).into_owned()),
+                token => {
+                    self.leading_comments = std::mem::take(&mut self.pending_comments);
+                    return Some(token);
+                }
+            }
+        }
+    }
+
     pub fn expect(&mut self, tkn: ProtoToken) -> Result<ProtoToken> {
-        let Some(got_token) = self.next() else {
+        let Some(got_token) = self.next_token() else {
             bail!("wanted {tkn} but received EOF")
         };
-        // Skip comments by recursively calling expect if it is one
-        if let ProtoToken::Comment(_) = got_token {
-            return self.expect(tkn);
-        }
         if tkn != got_token {
             bail!("wanted {tkn} but got {got_token}")
         }
@@ -259,152 +394,172 @@ impl<T: io::Read> Iterator for Scanner<T> {
 
     // TODO: Could we wrap this in a comment ignoring iter which just calls next again if it's a
     // comment?
-    // TODO: How do we handle errors? -> Use a Result<ProtoToken> as Item?
     fn next(&mut self) -> Option<Self::Item> {
         self.whitespace();
+        self.token_start = self.position;
         if self.is_done() {
             return None;
         }
 
-        if let Ok(Some(name)) = self.full_ident() {
-            if name.len() == 1 {
-                let name = &name[0];
-                let name_vec = name.to_vec();
-                let name_string = String::from_utf8(name_vec.clone()).unwrap();
-                // Keywords or identifier
-                return Some(match name_string.as_str() {
-                    "syntax" => ProtoToken::Syntax,
-                    "package" => ProtoToken::Package,
-                    "option" => ProtoToken::Option,
-                    "import" => ProtoToken::Import,
-                    "message" => ProtoToken::Message,
-                    "service" => ProtoToken::Service,
-                    "enum" => ProtoToken::Enum,
-                    "oneof" => ProtoToken::OneOf,
-                    "repeated" => ProtoToken::Repeated,
-                    "weak" => ProtoToken::Weak,
-                    "public" => ProtoToken::Public,
-                    "reserved" => ProtoToken::Reserved,
-                    "extend" => ProtoToken::Extend,
-                    "extensions" => ProtoToken::Extensions,
-                    "to" => ProtoToken::To,
-                    "max" => ProtoToken::Max,
-                    "map" => ProtoToken::Map,
-                    "inf" => todo!(),
-                    "optional" => unimplemented!("optional is unsupported"),
-                    "required" => unimplemented!("required is unsupported"),
-                    "bool" => ProtoToken::Bool,
-                    "string" => ProtoToken::String,
-                    "bytes" => ProtoToken::Bytes,
-                    "float" => ProtoToken::Float,
-                    "double" => ProtoToken::Double,
-                    "int32" => todo!(),
-                    "int64" => todo!(),
-                    "uint32" => todo!(),
-                    "uint64" => todo!(),
-                    "sint32" => todo!(),
-                    "sint64" => todo!(),
-                    "fixed32" => todo!(),
-                    "fixed64" => todo!(),
-                    "sfixed32" => todo!(),
-                    "sfixed64" => todo!(),
-                    "group" => unimplemented!("group is unsupported"),
-                    "rpc" => todo!(),
-                    "stream" => todo!(),
-                    "returns" => todo!(),
-                    _ => ProtoToken::Identifier(name_vec),
-                });
-            }
+        match self.full_ident() {
+            Ok(Some(name)) => {
+                if name.len() == 1 {
+                    return Some(keyword_or_identifier(&name[0]));
+                }
 
-            return Some(ProtoToken::FullIdentifier(name));
+                return Some(ProtoToken::FullIdentifier(name));
+            }
+            Ok(None) => {}
+            Err(_) => return Some(ProtoToken::Invalid),
         }
 
         if let Some(opt) = self.string() {
-            return match opt {
-                Ok(s) => Some(ProtoToken::StringLiteral(String::from_utf8(s).unwrap())),
-                Err(_) => None,
-            };
+            return Some(match opt.map(String::from_utf8) {
+                Ok(Ok(s)) => ProtoToken::StringLiteral(s),
+                Ok(Err(err)) => ProtoToken::BytesLiteral(err.into_bytes()),
+                Err(_) => ProtoToken::Invalid,
+            });
         }
 
-        if let Some(i) = self.int_literal() {
-            // TODO: Remove unwrap
-            return Some(ProtoToken::IntLiteral(
-                String::from_utf8(i).unwrap().parse().unwrap(),
-            ));
+        if let Some(number) = self.number() {
+            return Some(number);
         }
 
-        if let Some(c) = self.peek() {
-            let token = match c {
-                b';' => Some(ProtoToken::SemiColon),
-                b'=' => Some(ProtoToken::Equals),
-                b'{' => Some(ProtoToken::OpenBracket),
-                b'}' => Some(ProtoToken::CloseBracket),
-                b'(' => Some(ProtoToken::OpenParen),
-                b')' => Some(ProtoToken::CloseParen),
-                b'[' => Some(ProtoToken::OpenBrace),
-                b']' => Some(ProtoToken::CloseBrace),
-                b':' => Some(ProtoToken::Colon),
-                b'<' => Some(ProtoToken::LessThan),
-                b'>' => Some(ProtoToken::GreaterThan),
-                b',' => Some(ProtoToken::Comma),
-                b'/' => {
-                    self.pop();
-                    if let Some(c) = self.peek() {
-                        let token = match c {
-                            b'/' => {
-                                self.pop();
-                                Some(ProtoToken::Comment(
-                                    self.take_until_consume_including(
-                                        |c| c != &b'\n' && c != &b'\x00',
-                                        true,
-                                    )
-                                    .unwrap(),
-                                ))
-                            }
-                            b'*' => {
-                                self.pop();
-                                let mut buf = vec![];
-                                loop {
-                                    buf.append(
-                                        &mut self
-                                            .take_until_consume(|c| c != &b'*', false)
-                                            .unwrap(),
-                                    );
-                                    // pop '*'
-                                    self.pop();
-
-                                    let Some(ch) = self.peek() else {
-                                        panic!("EOF before end of multiline comment")
-                                    };
-
-                                    if ch == b'/' {
-                                        self.pop();
-                                        break;
-                                    }
-                                    buf.push(b'*');
-                                }
-
-                                Some(ProtoToken::Comment(buf))
-                            }
-                            _ => None,
-                        };
-                        return token;
+        let c = self.peek()?;
+        let token = match c {
+            b';' => ProtoToken::SemiColon,
+            b'=' => ProtoToken::Equals,
+            b'{' => ProtoToken::OpenBracket,
+            b'}' => ProtoToken::CloseBracket,
+            b'(' => ProtoToken::OpenParen,
+            b')' => ProtoToken::CloseParen,
+            b'[' => ProtoToken::OpenBrace,
+            b']' => ProtoToken::CloseBrace,
+            b':' => ProtoToken::Colon,
+            b'<' => ProtoToken::LessThan,
+            b'>' => ProtoToken::GreaterThan,
+            b',' => ProtoToken::Comma,
+            b'.' => ProtoToken::Dot,
+            b'-' => ProtoToken::Minus,
+            b'+' => ProtoToken::Plus,
+            b'/' => {
+                self.pop();
+                return Some(match self.peek() {
+                    Some(b'/') => {
+                        self.pop();
+                        match self
+                            .take_until_consume_including(|c| c != &b'\n' && c != &b'\x00', true)
+                        {
+                            Ok(comment) => ProtoToken::Comment(comment),
+                            Err(_) => ProtoToken::Invalid,
+                        }
                     }
-
-                    None
+                    Some(b'*') => {
+                        self.pop();
+                        self.multi_line_comment()
+                            .map_or(ProtoToken::Invalid, ProtoToken::Comment)
+                    }
+                    _ => ProtoToken::Invalid,
+                });
+            }
+            _ => {
+                // Consume the rest of a multi-byte character so an invalid token is never split
+                // part way through one
+                self.pop();
+                while matches!(self.peek(), Some(0x80..=0xbf)) {
+                    self.pop();
                 }
-                _ => None,
+                return Some(ProtoToken::Invalid);
+            }
+        };
+
+        self.pop();
+        Some(token)
+    }
+}
+
+impl<T: io::Read> Scanner<T> {
+    // multi_line_comment takes everything up to the closing `*/`, the opening `/*` should already
+    // be consumed
+    fn multi_line_comment(&mut self) -> Result<Vec<u8>> {
+        let mut buf = vec![];
+        loop {
+            buf.append(&mut self.take_until_consume(|c| c != &b'*', false)?);
+            // pop '*'
+            self.pop();
+
+            let Some(ch) = self.peek() else {
+                bail!("EOF before end of multiline comment")
             };
 
-            if token.is_some() {
+            if ch == b'/' {
                 self.pop();
+                return Ok(buf);
             }
-            return token;
+            buf.push(b'*');
         }
-        None
     }
 }
 
+fn keyword_or_identifier(name: &[u8]) -> ProtoToken {
+    match name {
+        b"syntax" => ProtoToken::Syntax,
+        b"package" => ProtoToken::Package,
+        b"option" => ProtoToken::Option,
+        b"import" => ProtoToken::Import,
+        b"message" => ProtoToken::Message,
+        b"service" => ProtoToken::Service,
+        b"enum" => ProtoToken::Enum,
+        b"oneof" => ProtoToken::OneOf,
+        b"repeated" => ProtoToken::Repeated,
+        b"weak" => ProtoToken::Weak,
+        b"public" => ProtoToken::Public,
+        b"reserved" => ProtoToken::Reserved,
+        b"extend" => ProtoToken::Extend,
+        b"extensions" => ProtoToken::Extensions,
+        b"to" => ProtoToken::To,
+        b"max" => ProtoToken::Max,
+        b"map" => ProtoToken::Map,
+        b"inf" => ProtoToken::Inf,
+        b"optional" => ProtoToken::Optional,
+        b"required" => ProtoToken::Required,
+        b"bool" => ProtoToken::Bool,
+        b"string" => ProtoToken::String,
+        b"bytes" => ProtoToken::Bytes,
+        b"float" => ProtoToken::Float,
+        b"double" => ProtoToken::Double,
+        b"int32" => ProtoToken::Int32,
+        b"int64" => ProtoToken::Int64,
+        b"uint32" => ProtoToken::Uint32,
+        b"uint64" => ProtoToken::Uint64,
+        b"sint32" => ProtoToken::Sint32,
+        b"sint64" => ProtoToken::Sint64,
+        b"fixed32" => ProtoToken::Fixed32,
+        b"fixed64" => ProtoToken::Fixed64,
+        b"sfixed32" => ProtoToken::Sfixed32,
+        b"sfixed64" => ProtoToken::Sfixed64,
+        b"group" => ProtoToken::Group,
+        b"rpc" => ProtoToken::Rpc,
+        b"stream" => ProtoToken::Stream,
+        b"returns" => ProtoToken::Returns,
+        _ => ProtoToken::Identifier(name.to_vec()),
+    }
+}
+
+fn int_token(digits: &[u8], radix: u32) -> ProtoToken {
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| u64::from_str_radix(digits, radix).ok())
+        .map_or(ProtoToken::Invalid, ProtoToken::IntLiteral)
+}
+
+fn float_token(digits: &[u8]) -> ProtoToken {
+    std::str::from_utf8(digits)
+        .ok()
+        .and_then(|digits| digits.parse().ok())
+        .map_or(ProtoToken::Invalid, ProtoToken::FloatLiteral)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -426,12 +581,42 @@ mod tests {
         syntax: ("syntax", ProtoToken::Syntax),
         full_ident: ("foo.bar.baz", ProtoToken::FullIdentifier(vec!["foo".into(), "bar".into(), "baz".into()])),
         int_literal: ("42", ProtoToken::IntLiteral(42)),
+        int_literal_zero: ("0", ProtoToken::IntLiteral(0)),
+        int_literal_hex: ("0x1F", ProtoToken::IntLiteral(31)),
+        int_literal_octal: ("017", ProtoToken::IntLiteral(15)),
+        int_literal_u64_max: ("18446744073709551615", ProtoToken::IntLiteral(u64::MAX)),
+        float_literal: ("1.5", ProtoToken::FloatLiteral(1.5)),
+        float_literal_leading_dot: (".5", ProtoToken::FloatLiteral(0.5)),
+        float_literal_exponent: ("1e-3", ProtoToken::FloatLiteral(0.001)),
+        keyword_int32: ("int32", ProtoToken::Int32),
+        keyword_optional: ("optional", ProtoToken::Optional),
+        keyword_returns: ("returns", ProtoToken::Returns),
+        identifier_underscore: ("_foo", ProtoToken::Identifier("_foo".into())),
+        dot: (".foo", ProtoToken::Dot),
+        minus: ("-1", ProtoToken::Minus),
         string_literal: (r#""string""#, ProtoToken::StringLiteral("string".to_owned())),
         string_literal_escaped: (r#""str\"ing""#, ProtoToken::StringLiteral(r#"str"ing"#.to_owned())),
+        string_literal_adjacent: ("\"a\" \n'b'", ProtoToken::StringLiteral("ab".to_owned())),
+        bytes_literal: (r#""\xff\0""#, ProtoToken::BytesLiteral(vec![0xff, 0])),
+        string_literal_escapes: (r#"'a\n\t\\\'\x41\101\0b'"#, ProtoToken::StringLiteral("a\n\t\\'AA\0b".to_owned())),
         single_line_comment: ("//comment\n", ProtoToken::Comment("comment".into())),
         single_line_comment_eof: ("//comment", ProtoToken::Comment("comment".into())),
         multi_line_comment: ("/*comment*/", ProtoToken::Comment("comment".into())),
         multi_line_comment_extra_asterisk: ("/*comm*ent*/", ProtoToken::Comment("comm*ent".into())),
         multi_line_comment_newlines: ("/*comm\nent*/", ProtoToken::Comment("comm\nent".into())),
+        unterminated_multi_line_comment: ("/*comment", ProtoToken::Invalid),
+        unterminated_string_literal: (r#""string"#, ProtoToken::Invalid),
+        invalid_character: ("£", ProtoToken::Invalid),
     );
+
+    #[test]
+    fn last_span() {
+        let mut scan: Scanner<&[u8]> = Scanner::new("syntax\n  = \"proto3\"".as_bytes());
+        scan.next();
+        scan.next();
+        assert_eq!(Position::new(9, 1, 2), scan.last_span().start());
+        scan.next();
+        assert_eq!(Position::new(11, 1, 4), scan.last_span().start());
+        assert_eq!(Position::new(19, 1, 12), scan.last_span().end());
+    }
 }
@@ -1,14 +1,28 @@
-#[derive(Debug, strum::Display, PartialEq, Eq)]
+#[derive(Debug, strum::Display, PartialEq)]
 pub enum ProtoToken {
     FullIdentifier(Vec<Vec<u8>>),
     Identifier(Vec<u8>),
     StringLiteral(String),
-    IntLiteral(isize),
+    /// A string literal whose escapes don't decode to valid UTF-8, only usable as `bytes`
+    BytesLiteral(Vec<u8>),
+    /// Int literals are unsigned, a leading `-` is scanned as a separate token
+    IntLiteral(u64),
+    FloatLiteral(f64),
     Bool,
     String,
     Bytes,
     Float,
     Double,
+    Int32,
+    Int64,
+    Uint32,
+    Uint64,
+    Sint32,
+    Sint64,
+    Fixed32,
+    Fixed64,
+    Sfixed32,
+    Sfixed64,
     Colon,
     SemiColon,
     Syntax,
@@ -20,6 +34,12 @@ pub enum ProtoToken {
     Enum,
     OneOf,
     Repeated,
+    Optional,
+    Required,
+    Group,
+    Rpc,
+    Stream,
+    Returns,
     OpenBrace,
     CloseBrace,
     OpenParen,
@@ -36,9 +56,68 @@ pub enum ProtoToken {
     To,
     /// Max is interpreted as 2,147,483,647
     Max,
+    Inf,
     Map,
     LessThan,
     GreaterThan,
     Comma,
+    Dot,
+    Minus,
+    Plus,
     Comment(Vec<u8>),
+    /// Characters which couldn't be scanned as any other token, such as an unterminated string
+    Invalid,
+}
+
+impl ProtoToken {
+    /// Keywords aren't reserved in protobuf, so any keyword can also be used as an identifier,
+    /// `string package = 1;` is a valid field
+    pub fn as_identifier(&self) -> Option<String> {
+        Some(
+            match self {
+                ProtoToken::Identifier(id) => return String::from_utf8(id.clone()).ok(),
+                ProtoToken::Bool => "bool",
+                ProtoToken::String => "string",
+                ProtoToken::Bytes => "bytes",
+                ProtoToken::Float => "float",
+                ProtoToken::Double => "double",
+                ProtoToken::Int32 => "int32",
+                ProtoToken::Int64 => "int64",
+                ProtoToken::Uint32 => "uint32",
+                ProtoToken::Uint64 => "uint64",
+                ProtoToken::Sint32 => "sint32",
+                ProtoToken::Sint64 => "sint64",
+                ProtoToken::Fixed32 => "fixed32",
+                ProtoToken::Fixed64 => "fixed64",
+                ProtoToken::Sfixed32 => "sfixed32",
+                ProtoToken::Sfixed64 => "sfixed64",
+                ProtoToken::Syntax => "syntax",
+                ProtoToken::Package => "package",
+                ProtoToken::Option => "option",
+                ProtoToken::Import => "import",
+                ProtoToken::Message => "message",
+                ProtoToken::Service => "service",
+                ProtoToken::Enum => "enum",
+                ProtoToken::OneOf => "oneof",
+                ProtoToken::Repeated => "repeated",
+                ProtoToken::Optional => "optional",
+                ProtoToken::Required => "required",
+                ProtoToken::Group => "group",
+                ProtoToken::Rpc => "rpc",
+                ProtoToken::Stream => "stream",
+                ProtoToken::Returns => "returns",
+                ProtoToken::Weak => "weak",
+                ProtoToken::Public => "public",
+                ProtoToken::Reserved => "reserved",
+                ProtoToken::Extend => "extend",
+                ProtoToken::Extensions => "extensions",
+                ProtoToken::To => "to",
+                ProtoToken::Max => "max",
+                ProtoToken::Inf => "inf",
+                ProtoToken::Map => "map",
+                _ => return None,
+            }
+            .to_owned(),
+        )
+    }
 }
@@ -0,0 +1,652 @@
+//! Renders a [`ProtoFile`] back to canonically formatted proto source.
+//!
+//! Declarations are printed grouped by kind in the order they're stored in the AST, so parsing
+//! the output gives back an equal file (apart from spans). Indentation is two spaces & leading
+//! comments are kept, line comments where possible & block comments otherwise.
+
+use std::fmt::Write;
+
+use crate::ast::{
+    MessageField, ProtoAggregateField, ProtoConstant, ProtoEnum, ProtoEnumValue, ProtoExtend,
+    ProtoFile, ProtoImportType, ProtoMessage, ProtoOneOf, ProtoOption, ProtoRange, ProtoRpc,
+    ProtoRpcType, ProtoService, ProtoSyntax,
+};
+use crate::parser::FIELD_NUMBER_MAX;
+
+const INDENT: &str = "  ";
+
+/// Prints `file` as proto source
+pub fn print(file: &ProtoFile) -> String {
+    let mut printer = Printer::default();
+    printer.file(file);
+    printer.out
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    depth: usize,
+}
+
+impl Printer {
+    fn line(&mut self, text: &str) {
+        for _ in 0..self.depth {
+            self.out.push_str(INDENT);
+        }
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    // separate adds a blank line, unless it's at the start of the file or a block
+    fn separate(&mut self) {
+        if !self.out.is_empty() && !self.out.ends_with("\n\n") && !self.out.ends_with("{\n") {
+            self.out.push('\n');
+        }
+    }
+
+    fn open(&mut self, text: &str) {
+        self.line(&format!("{text} {{"));
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+        // Empty blocks are closed on the same line, `message Foo {}`
+        if self.out.ends_with(" {\n") {
+            self.out.pop();
+            self.out.push_str("}\n");
+        } else {
+            self.line("}");
+        }
+    }
+
+    fn comments(&mut self, comments: &[String]) {
+        for comment in comments {
+            // Line comments can't hold newlines & would lose trailing whitespace to editors
+            let block = (comment.contains('\n') || comment.ends_with(char::is_whitespace))
+                && !comment.contains("*/");
+            if block {
+                self.line(&format!("/*{comment}*/"));
+            } else {
+                self.line(&format!("//{comment}"));
+            }
+        }
+    }
+
+    fn file(&mut self, file: &ProtoFile) {
+        let syntax = match file.syntax() {
+            ProtoSyntax::Proto2 => "proto2",
+            ProtoSyntax::Proto3 => "proto3",
+        };
+        self.comments(file.comments());
+        self.line(&format!("syntax = \"{syntax}\";"));
+
+        if !file.package().is_empty() {
+            self.separate();
+            self.line(&format!("package {};", file.package().join(".")));
+        }
+
+        if !file.imports().is_empty() {
+            self.separate();
+        }
+        for import in file.imports() {
+            self.comments(import.comments());
+            let modifier = match import.import_type() {
+                ProtoImportType::Default => "",
+                ProtoImportType::Weak => "weak ",
+                ProtoImportType::Public => "public ",
+            };
+            self.line(&format!("import {modifier}{};", string(import.path())));
+        }
+
+        self.options(file.options());
+        for message in file.messages() {
+            self.separate();
+            self.message(message);
+        }
+        for enumeration in file.enums() {
+            self.separate();
+            self.enumeration(enumeration);
+        }
+        for service in file.services() {
+            self.separate();
+            self.service(service);
+        }
+        for extend in file.extends() {
+            self.separate();
+            self.extend(extend);
+        }
+    }
+
+    // options prints option statements as their own section
+    fn options(&mut self, options: &[ProtoOption]) {
+        if options.is_empty() {
+            return;
+        }
+        self.separate();
+        for option in options {
+            self.comments(option.comments());
+            let value = constant(option.value(), self.depth);
+            self.line(&format!("option {} = {value};", option.name()));
+        }
+    }
+
+    fn message(&mut self, message: &ProtoMessage) {
+        self.comments(message.comments());
+        self.open(&format!("message {}", message.name()));
+        self.message_body(message);
+        self.close();
+    }
+
+    fn message_body(&mut self, message: &ProtoMessage) {
+        self.options(message.options());
+        if !message.reserved_ranges().is_empty() || !message.reserved_names().is_empty() {
+            self.separate();
+        }
+        self.reserved(
+            message.reserved_ranges(),
+            message.reserved_names(),
+            FIELD_NUMBER_MAX,
+        );
+        // Ranges declared together share their options, so each group is one statement
+        for group in message
+            .extension_ranges()
+            .chunk_by(|a, b| a.options() == b.options())
+        {
+            let ranges = ranges(group, FIELD_NUMBER_MAX);
+            let options = field_options(group[0].options(), self.depth);
+            self.line(&format!("extensions {ranges}{options};"));
+        }
+
+        if !message.fields().is_empty() {
+            self.separate();
+        }
+        for field in message.fields() {
+            self.field(field);
+        }
+        for oneof in message.oneofs() {
+            self.separate();
+            self.oneof(oneof);
+        }
+        for nested in message.messages() {
+            self.separate();
+            self.message(nested);
+        }
+        for enumeration in message.enums() {
+            self.separate();
+            self.enumeration(enumeration);
+        }
+        for extend in message.extends() {
+            self.separate();
+            self.extend(extend);
+        }
+        self.comments(message.trailing_comments());
+    }
+
+    fn reserved(&mut self, reserved: &[ProtoRange], names: &[String], max: i64) {
+        if !reserved.is_empty() {
+            self.line(&format!("reserved {};", ranges(reserved, max)));
+        }
+        if !names.is_empty() {
+            let names: Vec<_> = names.iter().map(|name| string(name)).collect();
+            self.line(&format!("reserved {};", names.join(", ")));
+        }
+    }
+
+    fn field(&mut self, field: &MessageField) {
+        self.comments(field.comments());
+        let label = field
+            .label()
+            .map(|label| format!("{label} "))
+            .unwrap_or_default();
+        let options: Vec<_> = field
+            .default()
+            .into_iter()
+            .chain(field.json_name_option())
+            .chain(field.options())
+            .cloned()
+            .collect();
+        let options = field_options(&options, self.depth);
+        if let Some(group) = field.group() {
+            self.open(&format!(
+                "{label}group {} = {}{options}",
+                group.name(),
+                field.index()
+            ));
+            self.message_body(group);
+            self.close();
+            return;
+        }
+        self.line(&format!(
+            "{label}{} {} = {}{options};",
+            field.field_type().name(),
+            field.name(),
+            field.index()
+        ));
+    }
+
+    fn oneof(&mut self, oneof: &ProtoOneOf) {
+        self.comments(oneof.comments());
+        self.open(&format!("oneof {}", oneof.name()));
+        self.options(oneof.options());
+        if !oneof.options().is_empty() {
+            self.separate();
+        }
+        for field in oneof.fields() {
+            self.field(field);
+        }
+        self.comments(oneof.trailing_comments());
+        self.close();
+    }
+
+    fn enumeration(&mut self, enumeration: &ProtoEnum) {
+        self.comments(enumeration.comments());
+        self.open(&format!("enum {}", enumeration.name()));
+
+        self.options(enumeration.options());
+        if !enumeration.reserved_ranges().is_empty() || !enumeration.reserved_names().is_empty() {
+            self.separate();
+        }
+        self.reserved(
+            enumeration.reserved_ranges(),
+            enumeration.reserved_names(),
+            i32::MAX.into(),
+        );
+
+        if !enumeration.values().is_empty() {
+            self.separate();
+        }
+        for value in enumeration.values() {
+            self.enum_value(value);
+        }
+        self.comments(enumeration.trailing_comments());
+        self.close();
+    }
+
+    fn enum_value(&mut self, value: &ProtoEnumValue) {
+        self.comments(value.comments());
+        let options = field_options(value.options(), self.depth);
+        self.line(&format!("{} = {}{options};", value.name(), value.number()));
+    }
+
+    fn service(&mut self, service: &ProtoService) {
+        self.comments(service.comments());
+        self.open(&format!("service {}", service.name()));
+        self.options(service.options());
+        if !service.rpcs().is_empty() {
+            self.separate();
+        }
+        for rpc in service.rpcs() {
+            self.rpc(rpc);
+        }
+        self.comments(service.trailing_comments());
+        self.close();
+    }
+
+    fn rpc(&mut self, rpc: &ProtoRpc) {
+        self.comments(rpc.comments());
+        let signature = format!(
+            "rpc {}({}) returns ({})",
+            rpc.name(),
+            rpc_type(rpc.request()),
+            rpc_type(rpc.returns())
+        );
+        if rpc.options().is_empty() {
+            self.line(&format!("{signature};"));
+            return;
+        }
+
+        self.open(&signature);
+        self.options(rpc.options());
+        self.close();
+    }
+
+    fn extend(&mut self, extend: &ProtoExtend) {
+        self.comments(extend.comments());
+        self.open(&format!("extend {}", extend.extendee().name()));
+        for field in extend.fields() {
+            self.field(field);
+        }
+        self.comments(extend.trailing_comments());
+        self.close();
+    }
+}
+
+fn rpc_type(rpc_type: &ProtoRpcType) -> String {
+    let stream = if rpc_type.is_stream() { "stream " } else { "" };
+    format!("{stream}{}", rpc_type.message_type().name())
+}
+
+// field_options prints the bracketed options after a field, enum value or extension range,
+// including the leading space
+fn field_options(options: &[ProtoOption], depth: usize) -> String {
+    if options.is_empty() {
+        return String::new();
+    }
+    let options: Vec<_> = options
+        .iter()
+        .map(|option| format!("{} = {}", option.name(), constant(option.value(), depth)))
+        .collect();
+    format!(" [{}]", options.join(", "))
+}
+
+fn ranges(ranges: &[ProtoRange], max: i64) -> String {
+    let ranges: Vec<_> = ranges
+        .iter()
+        .map(|range| match (range.start(), range.end()) {
+            (start, end) if start == end => start.to_string(),
+            (start, end) if end == max => format!("{start} to max"),
+            (start, end) => format!("{start} to {end}"),
+        })
+        .collect();
+    ranges.join(", ")
+}
+
+// constant prints an option value, aggregates are spread over several lines indented from depth
+fn constant(value: &ProtoConstant, depth: usize) -> String {
+    match value {
+        ProtoConstant::String(value) => string(value),
+        ProtoConstant::Bytes(value) => bytes(value),
+        ProtoConstant::Int(value) => value.to_string(),
+        ProtoConstant::Float(value) => float(*value),
+        ProtoConstant::Bool(value) => value.to_string(),
+        ProtoConstant::Identifier(value) => value.clone(),
+        ProtoConstant::Aggregate(fields) => aggregate(fields, depth),
+        ProtoConstant::List(values) => {
+            let values: Vec<_> = values.iter().map(|v| constant(v, depth)).collect();
+            format!("[{}]", values.join(", "))
+        }
+    }
+}
+
+fn aggregate(fields: &[ProtoAggregateField], depth: usize) -> String {
+    if fields.is_empty() {
+        return "{}".to_owned();
+    }
+
+    let mut out = "{\n".to_owned();
+    for field in fields {
+        let indent = INDENT.repeat(depth + 1);
+        let value = constant(field.value(), depth + 1);
+        // Message values don't need a colon
+        let separator = if let ProtoConstant::Aggregate(_) = field.value() {
+            " "
+        } else {
+            ": "
+        };
+        let _ = writeln!(out, "{indent}{}{separator}{value}", field.name());
+    }
+    out.push_str(&INDENT.repeat(depth));
+    out.push('}');
+    out
+}
+
+// float prints a float so it's always read back as a float, never an int
+fn float(value: f64) -> String {
+    let sign = if value.is_sign_negative() { "-" } else { "" };
+    if value.is_nan() {
+        format!("{sign}nan")
+    } else if value.is_infinite() {
+        format!("{sign}inf")
+    } else {
+        format!("{value:?}")
+    }
+}
+
+// bytes prints a string literal which isn't valid UTF-8, escaping anything outside of ASCII
+fn bytes(value: &[u8]) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for &b in value {
+        match b {
+            b'"' => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b' '..=b'~' => out.push(b as char),
+            b => {
+                let _ = write!(out, "\\{b:03o}");
+            }
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn string(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c.is_ascii_control() => {
+                let _ = write!(out, "\\{:03o}", c as u8);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // strip_spans removes the spans from a serialized file, so files parsed from different
+    // sources can be compared
+    fn strip_spans(file: &ProtoFile) -> serde_json::Value {
+        fn strip(value: &mut serde_json::Value) {
+            match value {
+                serde_json::Value::Object(map) => {
+                    map.remove("span");
+                    map.remove("package_span");
+                    map.values_mut().for_each(strip);
+                }
+                serde_json::Value::Array(values) => values.iter_mut().for_each(strip),
+                _ => {}
+            }
+        }
+
+        let mut value = serde_json::to_value(file).unwrap();
+        strip(&mut value);
+        value
+    }
+
+    const SOURCE: &str = r#"
+// Licence
+syntax = "proto2";
+package foo.bar.v1;
+
+import "a.proto";
+/* Re-exported */
+import public "b.proto";
+import weak "c.proto";
+
+// Where the Java classes go
+option java_package = "com.foo";
+option (custom.http) = { get: "/v1/{name=*}" [ext.field]: -2 nested { x: 1.0 y: [1, -2.5e-7, inf] } empty {} };
+option (custom.floats) = { a: -inf b: nan c: -0.0 d: 1e300 };
+
+// Documents Outer
+//   over two lines
+message Outer {
+    /* Replaced by Newer */
+    option deprecated = true;
+    reserved 2, 4 to 6, 15 to max;
+    reserved "old", "older";
+    extensions 100 to 199, 1000 to max;
+    extensions 200, 300 to 399 [verification = UNVERIFIED, (custom.range) = { note: "x" }];
+
+    /* Documents a */
+    optional int32 a = 1 [default = -5, (validate.rules).int32 = { gt: 0 }];
+    required string b = 3 [default = "quote \" slash \\ tab \t bell \a"];
+    repeated .foo.Other c = 7;
+    map<string, Outer.Inner> children = 8;
+    optional bytes d = 9 [json_name = "dee", default = "\xff\0a"];
+    repeated group Result = 12 [deprecated = true] {
+        optional string url = 13;
+    }
+
+    oneof choice {
+        // Custom
+        option (custom.oneof) = true;
+        fixed64 e = 10;
+        sfixed32 f = 11;
+    }
+
+    message Inner {
+        message Deeper {}
+    }
+
+    enum Kind {
+        // Allows KIND_DEFAULT
+        option allow_alias = true;
+        reserved 5 to max;
+        reserved "KIND_OLD";
+        KIND_UNKNOWN = 0;
+        // Alias
+        KIND_DEFAULT = 0 [deprecated = true];
+        KIND_NEGATIVE = -1;
+    }
+
+    extend Other {
+        optional uint64 id = 101;
+    }
+}
+
+enum Top { TOP_UNKNOWN = 0; }
+
+service Svc {
+    option (custom.service) = "svc";
+    rpc Get(stream Outer) returns (Outer.Inner);
+    // Lists
+    rpc List(Outer) returns (stream foo.bar.v1.Outer) {
+        // Safe to retry
+        option idempotency_level = NO_SIDE_EFFECTS;
+        option (custom.http) = { get: "/v1/list" };
+    }
+}
+
+extend google.protobuf.FieldOptions {
+    repeated sint64 tags = 50000;
+}
+"#;
+
+    #[test]
+    fn round_trip() {
+        let file = crate::parse(SOURCE).unwrap();
+        let printed = print(&file);
+        let reparsed = crate::parse(&printed).unwrap_or_else(|err| panic!("{err}\n{printed}"));
+
+        assert_eq!(strip_spans(&file), strip_spans(&reparsed));
+        // Printing is canonical so printing the output again changes nothing
+        assert_eq!(printed, print(&reparsed));
+    }
+
+    #[test]
+    fn round_trip_comments() {
+        let source = r#"// Licence
+syntax = "proto3";
+
+// Where the Java classes go
+option java_package = "com.foo";
+
+// Documents Foo
+message Foo {
+  /* Replaced by Bar */
+  option deprecated = true;
+
+  // Documents a
+  int32 a = 1;
+
+  // Documents o
+  oneof o {
+    // Documents b
+    int32 b = 2;
+    // After b
+  }
+
+  // Documents Kind
+  enum Kind {
+    // Documents KIND_UNKNOWN
+    KIND_UNKNOWN = 0;
+    // After KIND_UNKNOWN
+  }
+
+  extend Foo {
+    int32 c = 100;
+    // After c
+  }
+  // After the nested declarations
+}
+
+message Empty {
+  // Nothing yet
+}
+
+service Svc {
+  // Documents Get
+  rpc Get(Foo) returns (Foo) {
+    // Safe to retry
+    option idempotency_level = NO_SIDE_EFFECTS;
+  }
+  // After Get
+}
+"#;
+
+        assert_eq!(source, print(&crate::parse(source).unwrap()));
+    }
+
+    #[test]
+    fn canonical_format() {
+        let file = crate::parse(
+            r#"syntax="proto3";package foo;import "a.proto";
+            // Foo
+            message Foo{reserved 1,2 to max;string a=3[deprecated=true];oneof o{int32 b=4;}
+            message Bar{}enum E{E_UNKNOWN=0;}}
+            service S{rpc A(Foo)returns(stream Foo){option (x)={a:1 b{c:"d"}};}}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            r#"syntax = "proto3";
+
+package foo;
+
+import "a.proto";
+
+// Foo
+message Foo {
+  reserved 1, 2 to max;
+
+  string a = 3 [deprecated = true];
+
+  oneof o {
+    int32 b = 4;
+  }
+
+  message Bar {}
+
+  enum E {
+    E_UNKNOWN = 0;
+  }
+}
+
+service S {
+  rpc A(Foo) returns (stream Foo) {
+    option (x) = {
+      a: 1
+      b {
+        c: "d"
+      }
+    };
+  }
+}
+"#,
+            print(&file)
+        );
+    }
+}
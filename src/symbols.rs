@@ -0,0 +1,924 @@
+//! An index of everything declared across a set of files by fully qualified name.
+//!
+//! Names follow protoc's scoping: packages & messages introduce scopes, enum values are declared
+//! alongside their enum rather than within it, oneof fields belong to the message & extensions
+//! belong to the scope their `extend` block is in.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use crate::ast::{
+    MessageField, ProtoConstant, ProtoEnum, ProtoEnumValue, ProtoExtend, ProtoFieldLabel,
+    ProtoFieldType, ProtoFile, ProtoMessage, ProtoOneOf, ProtoOption, ProtoRange, ProtoRpc,
+    ProtoService, ProtoSyntax,
+};
+use crate::span::Span;
+use crate::visit::{self, Context, Visitor};
+
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, Hash)]
+#[strum(serialize_all = "snake_case")]
+pub enum SymbolKind {
+    Package,
+    Message,
+    Enum,
+    EnumValue,
+    Service,
+    Rpc,
+    Field,
+    OneOf,
+    Extension,
+}
+
+impl SymbolKind {
+    /// Whether the symbol can be used as the type of a field
+    pub fn is_type(self) -> bool {
+        matches!(self, Self::Message | Self::Enum)
+    }
+
+    // is_aggregate is whether other symbols can be declared within the symbol's name, so a name
+    // resolving to it may continue to a symbol within it
+    fn is_aggregate(self) -> bool {
+        matches!(
+            self,
+            Self::Package | Self::Message | Self::Enum | Self::Service
+        )
+    }
+}
+
+/// Something declared at a fully qualified name
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    full_name: String,
+    kind: SymbolKind,
+    parent: String,
+    file: String,
+    syntax: ProtoSyntax,
+    field_type: Option<ProtoFieldType>,
+    repeated: bool,
+    extendee: Option<ProtoFieldType>,
+    number: Option<i64>,
+    extension_ranges: Vec<ProtoRange>,
+    deprecated: bool,
+    span: Span,
+}
+
+impl Symbol {
+    /// The fully qualified name without a leading dot, `foo.bar.v1.TestMessage.amount`
+    pub fn full_name(&self) -> &str {
+        &self.full_name
+    }
+
+    /// The last part of the full name
+    pub fn name(&self) -> &str {
+        self.full_name
+            .rsplit_once('.')
+            .map_or(&self.full_name, |(_, name)| name)
+    }
+
+    /// The scope the symbol is declared in, empty at the root
+    pub fn scope(&self) -> &str {
+        self.full_name
+            .rsplit_once('.')
+            .map_or("", |(scope, _)| scope)
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    /// The full name of the declaration the symbol belongs to. It's the same as the scope other
+    /// than for enum values, which belong to their enum.
+    pub fn parent(&self) -> &str {
+        &self.parent
+    }
+
+    /// The name of the file the symbol is declared in
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The syntax of the file the symbol is declared in, proto2 enums are closed for example
+    pub fn syntax(&self) -> ProtoSyntax {
+        self.syntax
+    }
+
+    /// The type of a field or extension as written, it resolves from the symbol's scope
+    pub fn field_type(&self) -> Option<&ProtoFieldType> {
+        self.field_type.as_ref()
+    }
+
+    /// Whether a field or extension is repeated, map fields are too
+    pub fn repeated(&self) -> bool {
+        self.repeated
+    }
+
+    /// The message an extension extends as written, it resolves from the symbol's scope
+    pub fn extendee(&self) -> Option<&ProtoFieldType> {
+        self.extendee.as_ref()
+    }
+
+    /// The number of a field, extension or enum value
+    pub fn number(&self) -> Option<i64> {
+        self.number
+    }
+
+    /// The ranges of a message's numbers reserved for extensions, `extensions 100 to max;`
+    pub fn extension_ranges(&self) -> &[ProtoRange] {
+        &self.extension_ranges
+    }
+
+    /// Whether the declaration sets `deprecated = true`
+    pub fn deprecated(&self) -> bool {
+        self.deprecated
+    }
+
+    /// The span of the declaration, packages don't have one so it's empty for them
+    pub fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Every symbol declared within the loaded files
+#[derive(Debug, Default, Clone)]
+pub struct SymbolTable {
+    // Every declaration of each name in the order their files were added, more than one is only
+    // valid for packages
+    symbols: BTreeMap<String, Vec<Symbol>>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the symbols declared in `file`, replacing any previously added from the same file
+    pub fn add_file(&mut self, name: &str, file: &ProtoFile) {
+        self.remove_file(name);
+
+        let mut collector = Collector {
+            file: name,
+            syntax: *file.syntax(),
+            extendee: None,
+            symbols: vec![],
+        };
+        visit::visit(&mut collector, file);
+        for symbol in collector.symbols {
+            self.symbols
+                .entry(symbol.full_name.clone())
+                .or_default()
+                .push(symbol);
+        }
+    }
+
+    /// Removes every symbol declared in the file called `name`
+    pub fn remove_file(&mut self, name: &str) {
+        self.symbols.retain(|_, symbols| {
+            symbols.retain(|symbol| symbol.file != name);
+            !symbols.is_empty()
+        });
+    }
+
+    /// The symbol declared at a fully qualified name, the first added when there are several. A
+    /// leading dot is ignored.
+    pub fn lookup(&self, full_name: &str) -> Option<&Symbol> {
+        self.definitions(full_name).first()
+    }
+
+    /// Every declaration of a fully qualified name, more than one for a package spread across
+    /// files or a name declared twice
+    pub fn definitions(&self, full_name: &str) -> &[Symbol] {
+        let full_name = full_name.strip_prefix('.').unwrap_or(full_name);
+        self.symbols.get(full_name).map_or(&[], Vec::as_slice)
+    }
+
+    /// The symbols declared directly within `scope`, use an empty scope for the root
+    pub fn children<'a>(&'a self, scope: &'a str) -> impl Iterator<Item = &'a Symbol> + 'a {
+        self.children_by(scope, |_| true)
+    }
+
+    /// Every symbol declared within the file called `name`
+    pub fn file_symbols<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Symbol> + 'a {
+        self.symbols
+            .values()
+            .flatten()
+            .filter(move |symbol| symbol.file == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Symbol> {
+        self.symbols.values().flatten()
+    }
+
+    /// Resolves the message or enum `name` used within `scope` following protoc's rules. Relative
+    /// names are searched for from the innermost scope outwards by their first part, once that's
+    /// found the rest of the name must be declared within it. Names starting with a dot are
+    /// absolute.
+    pub fn resolve_type(&self, scope: &str, name: &str) -> Result<&Symbol, Unresolved> {
+        self.resolve_by(scope, name, Wanted::Type, |_| true)
+    }
+
+    /// Resolves the extension `name` used within `scope`, such as the name of a custom option, with
+    /// the same rules as [`SymbolTable::resolve_type`]
+    pub fn resolve_extension(&self, scope: &str, name: &str) -> Result<&Symbol, Unresolved> {
+        self.resolve_by(scope, name, Wanted::Extension, |_| true)
+    }
+
+    /// The symbols which can be used by a file, those declared within the files called `files`
+    pub fn visible_to<I, S>(&self, files: I) -> VisibleSymbols<'_>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        VisibleSymbols {
+            table: self,
+            files: files.into_iter().map(Into::into).collect(),
+            indirect: HashMap::new(),
+            imports_loaded: true,
+        }
+    }
+
+    fn lookup_by(&self, full_name: &str, visible: impl Fn(&Symbol) -> bool) -> Option<&Symbol> {
+        self.definitions(full_name)
+            .iter()
+            .find(|symbol| visible(symbol))
+    }
+
+    fn children_by<'a>(
+        &'a self,
+        scope: &'a str,
+        visible: impl Fn(&Symbol) -> bool + 'a,
+    ) -> impl Iterator<Item = &'a Symbol> + 'a {
+        let scope = scope.strip_prefix('.').unwrap_or(scope);
+        let prefix = if scope.is_empty() {
+            String::new()
+        } else {
+            format!("{scope}.")
+        };
+        self.symbols
+            .range(prefix.clone()..)
+            .take_while(move |(name, _)| name.starts_with(&prefix))
+            .filter_map(move |(_, symbols)| symbols.iter().find(|symbol| visible(symbol)))
+            .filter(move |symbol| symbol.scope() == scope)
+    }
+
+    fn resolve_by(
+        &self,
+        scope: &str,
+        name: &str,
+        wanted: Wanted,
+        visible: impl Fn(&Symbol) -> bool,
+    ) -> Result<&Symbol, Unresolved> {
+        let lookup_wanted = |full_name: &str| {
+            self.lookup_by(full_name, |symbol| {
+                wanted.matches(symbol.kind) && visible(symbol)
+            })
+        };
+        let mut unresolved = Unresolved {
+            wanted,
+            name: name.to_owned(),
+            tried: vec![],
+            shadowed_by: None,
+            not_imported: None,
+            imported_through: None,
+        };
+        if let Some(absolute) = name.strip_prefix('.') {
+            unresolved.tried.push(absolute.to_owned());
+            return lookup_wanted(absolute).ok_or(unresolved);
+        }
+
+        let (first, rest) = match name.split_once('.') {
+            Some((first, rest)) => (first, Some(rest)),
+            None => (name, None),
+        };
+        let mut scope = Some(scope.strip_prefix('.').unwrap_or(scope));
+        while let Some(current) = scope {
+            scope = (!current.is_empty())
+                .then(|| current.rsplit_once('.').map_or("", |(parent, _)| parent));
+
+            let candidate = join(current, first);
+            unresolved.tried.push(join(current, name));
+            let Some(symbol) = self.lookup_by(&candidate, &visible) else {
+                continue;
+            };
+            match rest {
+                // Checking every declaration of the name, as a duplicate may be the one wanted
+                None => {
+                    if let Some(symbol) = lookup_wanted(&candidate) {
+                        return Ok(symbol);
+                    }
+                }
+                // The first part only shadows outer scopes when the rest could be within it
+                Some(rest) if symbol.kind.is_aggregate() => {
+                    if let Some(symbol) = lookup_wanted(&join(&candidate, rest)) {
+                        return Ok(symbol);
+                    }
+                    // Only worth mentioning when the name would resolve from the outermost scope
+                    if !current.is_empty() && lookup_wanted(name).is_some() {
+                        unresolved.shadowed_by = Some(candidate);
+                    }
+                    return Err(unresolved);
+                }
+                _ => {}
+            }
+        }
+        Err(unresolved)
+    }
+}
+
+/// The symbols a file can use, those declared in the file itself & the files it imports
+#[derive(Debug, Clone)]
+pub struct VisibleSymbols<'a> {
+    table: &'a SymbolTable,
+    files: HashSet<String>,
+    // Files which are only imported by imports, by the direct import they're reached through
+    indirect: HashMap<String, String>,
+    imports_loaded: bool,
+}
+
+impl<'a> VisibleSymbols<'a> {
+    /// Records the files which aren't visible but are imported by the file's imports, each with the
+    /// import it's reached through, so names declared in them are reported as such
+    pub fn with_indirect<I>(mut self, indirect: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        self.indirect.extend(indirect);
+        self
+    }
+
+    /// Records that the visible files have imports which weren't loaded, so names which don't
+    /// resolve may be declared in them
+    pub fn without_imports(mut self) -> Self {
+        self.imports_loaded = false;
+        self
+    }
+
+    /// Whether every import of the visible files is loaded, when it isn't names which don't
+    /// resolve shouldn't be reported
+    pub fn imports_loaded(&self) -> bool {
+        self.imports_loaded
+    }
+
+    /// Every symbol including those which aren't visible, for resolving names used by the files
+    /// declaring the visible symbols
+    pub fn table(&self) -> &'a SymbolTable {
+        self.table
+    }
+
+    /// Whether the file called `name` is visible
+    pub fn contains_file(&self, name: &str) -> bool {
+        self.files.contains(name)
+    }
+
+    /// The visible symbol declared at a fully qualified name
+    pub fn lookup(&self, full_name: &str) -> Option<&'a Symbol> {
+        self.table
+            .lookup_by(full_name, |symbol| self.files.contains(&symbol.file))
+    }
+
+    /// The visible symbols declared directly within `scope`
+    pub fn children<'b>(&'b self, scope: &'b str) -> impl Iterator<Item = &'b Symbol> + 'b {
+        self.table
+            .children_by(scope, |symbol| self.files.contains(&symbol.file))
+    }
+
+    /// Resolves a type like [`SymbolTable::resolve_type`] from the visible symbols. When the type
+    /// would resolve to one in a file which isn't visible, that file is included in the error.
+    pub fn resolve_type(&self, scope: &str, name: &str) -> Result<&'a Symbol, Unresolved> {
+        self.resolve_by(scope, name, Wanted::Type)
+    }
+
+    /// Resolves an extension like [`SymbolTable::resolve_extension`] from the visible symbols
+    pub fn resolve_extension(&self, scope: &str, name: &str) -> Result<&'a Symbol, Unresolved> {
+        self.resolve_by(scope, name, Wanted::Extension)
+    }
+
+    fn resolve_by(
+        &self,
+        scope: &str,
+        name: &str,
+        wanted: Wanted,
+    ) -> Result<&'a Symbol, Unresolved> {
+        self.table
+            .resolve_by(scope, name, wanted, |symbol| {
+                self.files.contains(&symbol.file)
+            })
+            .map_err(|mut unresolved| {
+                if let Ok(symbol) = self.table.resolve_by(scope, name, wanted, |_| true) {
+                    unresolved.not_imported = Some(symbol.file.clone());
+                    unresolved.imported_through = self
+                        .indirect
+                        .get(&symbol.file)
+                        .map(|through| through.as_str().into());
+                }
+                unresolved
+            })
+    }
+}
+
+// Wanted is the kind of symbol a name is resolved to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Wanted {
+    Type,
+    Extension,
+}
+
+impl Wanted {
+    fn matches(self, kind: SymbolKind) -> bool {
+        match self {
+            Self::Type => kind.is_type(),
+            Self::Extension => kind == SymbolKind::Extension,
+        }
+    }
+}
+
+fn join(scope: &str, name: &str) -> String {
+    if scope.is_empty() {
+        name.to_owned()
+    } else {
+        format!("{scope}.{name}")
+    }
+}
+
+/// A type name which didn't resolve to a message or enum, or an extension name which didn't resolve
+/// to an extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unresolved {
+    wanted: Wanted,
+    name: String,
+    tried: Vec<String>,
+    shadowed_by: Option<String>,
+    not_imported: Option<String>,
+    // Boxed to keep results holding the error small
+    imported_through: Option<Box<str>>,
+}
+
+impl Unresolved {
+    /// The name as it's written
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The full names searched for, innermost scope first
+    pub fn tried(&self) -> &[String] {
+        &self.tried
+    }
+
+    /// The symbol the first part of the name resolved to when the rest of the name isn't declared
+    /// within it, which stops outer scopes being searched
+    pub fn shadowed_by(&self) -> Option<&str> {
+        self.shadowed_by.as_deref()
+    }
+
+    /// The file declaring the symbol when it isn't imported by the file using it
+    pub fn not_imported(&self) -> Option<&str> {
+        self.not_imported.as_deref()
+    }
+
+    /// The import which imports the file declaring the symbol, when the file isn't imported
+    /// directly but is through one of its imports
+    pub fn imported_through(&self) -> Option<&str> {
+        self.imported_through.as_deref()
+    }
+}
+
+impl fmt::Display for Unresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let wanted = match self.wanted {
+            Wanted::Type => "type",
+            Wanted::Extension => "extension",
+        };
+        write!(f, "unresolved {wanted} {}, tried ", self.name)?;
+        for (i, name) in self.tried.iter().enumerate() {
+            match i {
+                0 => {}
+                i if i + 1 == self.tried.len() => write!(f, " & ")?,
+                _ => write!(f, ", ")?,
+            }
+            write!(f, "{name}")?;
+        }
+        if let Some(shadowed_by) = &self.shadowed_by {
+            write!(
+                f,
+                "; {shadowed_by} is found first & hides outer scopes, use .{} to start from \
+                 the outermost scope",
+                self.name
+            )?;
+        }
+        match (&self.not_imported, &self.imported_through) {
+            (Some(file), Some(through)) => write!(
+                f,
+                "; it's declared in {file}, which is only imported indirectly through {through}, \
+                 import it directly"
+            )?,
+            (Some(file), None) => write!(f, "; it's declared in {file}, which isn't imported")?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+struct Collector<'a> {
+    file: &'a str,
+    syntax: ProtoSyntax,
+    // The extendee of the `extend` block being visited
+    extendee: Option<ProtoFieldType>,
+    symbols: Vec<Symbol>,
+}
+
+impl Collector<'_> {
+    fn add(&mut self, full_name: String, kind: SymbolKind, span: Span) {
+        let parent = full_name
+            .rsplit_once('.')
+            .map_or("", |(scope, _)| scope)
+            .to_owned();
+        self.add_with_parent(full_name, kind, parent, span);
+    }
+
+    fn add_with_parent(&mut self, full_name: String, kind: SymbolKind, parent: String, span: Span) {
+        self.symbols.push(Symbol {
+            full_name,
+            kind,
+            parent,
+            file: self.file.to_owned(),
+            syntax: self.syntax,
+            field_type: None,
+            repeated: false,
+            extendee: None,
+            number: None,
+            extension_ranges: vec![],
+            deprecated: false,
+            span,
+        });
+    }
+
+    // add_deprecated adds a symbol, recording whether its options deprecate it
+    fn add_deprecated(
+        &mut self,
+        full_name: String,
+        kind: SymbolKind,
+        span: Span,
+        options: &[ProtoOption],
+    ) {
+        self.add(full_name, kind, span);
+        if let Some(symbol) = self.symbols.last_mut() {
+            symbol.deprecated = deprecated(options);
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for Collector<'_> {
+    fn visit_file(&mut self, ctx: &mut Context, file: &'ast ProtoFile) {
+        // Each part of a package is a scope of its own, `foo.bar` declares `foo` & `foo.bar`
+        for i in 1..=file.package().len() {
+            self.add(
+                file.package()[..i].join("."),
+                SymbolKind::Package,
+                Span::default(),
+            );
+        }
+        visit::walk_file(self, ctx, file);
+    }
+
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        let name = ctx.full_name(message.name());
+        self.add_deprecated(name, SymbolKind::Message, message.span(), message.options());
+        if let Some(symbol) = self.symbols.last_mut() {
+            symbol.extension_ranges = message.extension_ranges().to_vec();
+        }
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        let kind = if ctx.in_extend() {
+            SymbolKind::Extension
+        } else {
+            SymbolKind::Field
+        };
+        self.add_deprecated(
+            ctx.full_name(field.name()),
+            kind,
+            field.span(),
+            field.options(),
+        );
+        if let Some(symbol) = self.symbols.last_mut() {
+            symbol.field_type = Some(field.field_type().clone());
+            symbol.repeated = field.label() == Some(ProtoFieldLabel::Repeated)
+                || matches!(field.field_type(), ProtoFieldType::Map { .. });
+            symbol.number = Some(field.index().into());
+            if ctx.in_extend() {
+                symbol.extendee = self.extendee.clone();
+            }
+        }
+        // Map fields declare the entry message protoc synthesizes with its key & value fields
+        if let (Some(entry), ProtoFieldType::Map { key, value }) =
+            (field.map_entry_name(), field.field_type())
+        {
+            let entry = ctx.full_name(&entry);
+            self.add(entry.clone(), SymbolKind::Message, field.span());
+            for (number, name, field_type) in [(1, "key", key), (2, "value", value)] {
+                self.add(format!("{entry}.{name}"), SymbolKind::Field, field.span());
+                if let Some(symbol) = self.symbols.last_mut() {
+                    symbol.field_type = Some(field_type.as_ref().clone());
+                    symbol.number = Some(number);
+                }
+            }
+        }
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        self.extendee = Some(extend.extendee().clone());
+        visit::walk_extend(self, ctx, extend);
+        self.extendee = None;
+    }
+
+    fn visit_oneof(&mut self, ctx: &mut Context, oneof: &'ast ProtoOneOf) {
+        self.add(ctx.full_name(oneof.name()), SymbolKind::OneOf, oneof.span());
+        visit::walk_oneof(self, ctx, oneof);
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, enumeration: &'ast ProtoEnum) {
+        let name = ctx.full_name(enumeration.name());
+        self.add_deprecated(
+            name,
+            SymbolKind::Enum,
+            enumeration.span(),
+            enumeration.options(),
+        );
+        visit::walk_enum(self, ctx, enumeration);
+    }
+
+    fn visit_enum_value(&mut self, ctx: &mut Context, value: &'ast ProtoEnumValue) {
+        let enumeration = ctx.full_name(ctx.enumeration().unwrap_or_default());
+        self.add_with_parent(
+            ctx.full_name(value.name()),
+            SymbolKind::EnumValue,
+            enumeration,
+            value.span(),
+        );
+        if let Some(symbol) = self.symbols.last_mut() {
+            symbol.number = Some(value.number().into());
+            symbol.deprecated = deprecated(value.options());
+        }
+    }
+
+    fn visit_service(&mut self, ctx: &mut Context, service: &'ast ProtoService) {
+        let name = ctx.full_name(service.name());
+        self.add_deprecated(name, SymbolKind::Service, service.span(), service.options());
+        visit::walk_service(self, ctx, service);
+    }
+
+    fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+        let service = ctx.service().unwrap_or_default();
+        let name = format!("{}.{}", ctx.full_name(service), rpc.name());
+        self.add_deprecated(name, SymbolKind::Rpc, rpc.span(), rpc.options());
+    }
+}
+
+// deprecated is whether options include `deprecated = true`
+fn deprecated(options: &[ProtoOption]) -> bool {
+    options
+        .iter()
+        .any(|option| option.name() == "deprecated" && *option.value() == ProtoConstant::Bool(true))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOO: &str = r#"syntax = "proto2";
+package foo.bar.v1;
+
+message TestMessage {
+    optional Amount amount = 1;
+    oneof choice {
+        int32 a = 2;
+    }
+    message Amount {}
+    enum Kind { KIND_UNKNOWN = 0; }
+    extend Other { optional int32 nested_ext = 100; }
+    extensions 100 to 200;
+}
+
+service TestService {
+    rpc Get(TestMessage) returns (TestMessage);
+}
+
+extend TestMessage { optional string tag = 100; }
+"#;
+
+    const OTHER: &str = r#"syntax = "proto3";
+package foo.bar.v1;
+message Other {}
+"#;
+
+    fn table() -> SymbolTable {
+        let mut table = SymbolTable::new();
+        table.add_file("foo.proto", &crate::parse(FOO).unwrap());
+        table.add_file("other.proto", &crate::parse(OTHER).unwrap());
+        table
+    }
+
+    #[test]
+    fn full_names() {
+        let table = table();
+        let kinds: Vec<_> = table
+            .file_symbols("foo.proto")
+            .map(|symbol| (symbol.full_name(), symbol.kind()))
+            .collect();
+
+        assert_eq!(
+            vec![
+                ("foo", SymbolKind::Package),
+                ("foo.bar", SymbolKind::Package),
+                ("foo.bar.v1", SymbolKind::Package),
+                ("foo.bar.v1.TestMessage", SymbolKind::Message),
+                ("foo.bar.v1.TestMessage.Amount", SymbolKind::Message),
+                ("foo.bar.v1.TestMessage.KIND_UNKNOWN", SymbolKind::EnumValue),
+                ("foo.bar.v1.TestMessage.Kind", SymbolKind::Enum),
+                ("foo.bar.v1.TestMessage.a", SymbolKind::Field),
+                ("foo.bar.v1.TestMessage.amount", SymbolKind::Field),
+                ("foo.bar.v1.TestMessage.choice", SymbolKind::OneOf),
+                ("foo.bar.v1.TestMessage.nested_ext", SymbolKind::Extension),
+                ("foo.bar.v1.TestService", SymbolKind::Service),
+                ("foo.bar.v1.TestService.Get", SymbolKind::Rpc),
+                ("foo.bar.v1.tag", SymbolKind::Extension),
+            ],
+            kinds
+        );
+    }
+
+    #[test]
+    fn lookup() {
+        let table = table();
+
+        let amount = table.lookup(".foo.bar.v1.TestMessage.amount").unwrap();
+        assert_eq!("foo.proto", amount.file());
+        assert_eq!("amount", amount.name());
+        assert_eq!("foo.bar.v1.TestMessage", amount.scope());
+        assert_eq!(4, amount.span().start().line());
+
+        assert_eq!(
+            "other.proto",
+            table.lookup("foo.bar.v1.Other").unwrap().file()
+        );
+        // Packages are declared by each file using them
+        assert_eq!(2, table.definitions("foo.bar").len());
+        assert!(table.lookup("foo.bar.v1.Missing").is_none());
+    }
+
+    #[test]
+    fn children() {
+        let table = table();
+        let names = |scope| table.children(scope).map(Symbol::name).collect::<Vec<_>>();
+
+        assert_eq!(vec!["foo"], names(""));
+        assert_eq!(
+            vec!["Other", "TestMessage", "TestService", "tag"],
+            names("foo.bar.v1")
+        );
+        assert_eq!(
+            vec![
+                "Amount",
+                "KIND_UNKNOWN",
+                "Kind",
+                "a",
+                "amount",
+                "choice",
+                "nested_ext"
+            ],
+            names("foo.bar.v1.TestMessage")
+        );
+    }
+
+    #[test]
+    fn resolve_type() {
+        let mut table = table();
+        table.add_file(
+            "scopes.proto",
+            &crate::parse(
+                r#"syntax = "proto3";
+package foo.baz;
+message Result {}
+message Outer {
+    message foo { message Other {} }
+    int32 Result = 1;
+}"#,
+            )
+            .unwrap(),
+        );
+        let resolve = |scope, name| {
+            table
+                .resolve_type(scope, name)
+                .map(Symbol::full_name)
+                .map_err(|err| err.to_string())
+        };
+
+        assert_eq!(
+            Ok("foo.bar.v1.TestMessage.Amount"),
+            resolve("foo.bar.v1.TestMessage", "Amount")
+        );
+        assert_eq!(
+            Ok("foo.bar.v1.TestMessage.Kind"),
+            resolve("foo.bar.v1.TestMessage.Amount", "TestMessage.Kind")
+        );
+        assert_eq!(
+            Ok("foo.bar.v1.Other"),
+            resolve("foo.bar.v1", ".foo.bar.v1.Other")
+        );
+        // foo.bar.v1.baz & foo.bar.baz aren't declared, the package foo.baz is
+        assert_eq!(Ok("foo.baz.Result"), resolve("foo.bar.v1", "baz.Result"));
+        // Fields aren't types so the search carries on past Outer.Result
+        assert_eq!(Ok("foo.baz.Result"), resolve("foo.baz.Outer", "Result"));
+
+        assert_eq!(
+            Err(
+                "unresolved type Missing, tried foo.bar.v1.Missing, foo.bar.Missing, \
+                 foo.Missing & Missing"
+                    .into()
+            ),
+            resolve("foo.bar.v1", "Missing")
+        );
+        assert_eq!(
+            Err("unresolved type .Other, tried Other".into()),
+            resolve("foo.bar.v1", ".Other")
+        );
+        assert_eq!(
+            Err(
+                "unresolved type foo.bar.v1.Other, tried foo.baz.Outer.foo.bar.v1.Other; \
+                 foo.baz.Outer.foo is found first & hides outer scopes, use .foo.bar.v1.Other \
+                 to start from the outermost scope"
+                    .into()
+            ),
+            resolve("foo.baz.Outer", "foo.bar.v1.Other")
+        );
+        assert_eq!(
+            Ok("foo.bar.v1.Other"),
+            resolve("foo.baz.Outer", ".foo.bar.v1.Other")
+        );
+    }
+
+    #[test]
+    fn resolve_extension() {
+        let table = table();
+        let tag = table
+            .resolve_extension("foo.bar.v1.TestMessage", "tag")
+            .unwrap();
+        assert_eq!("foo.bar.v1.tag", tag.full_name());
+        assert_eq!(Some(&ProtoFieldType::String), tag.field_type());
+        assert_eq!(
+            Some(&ProtoFieldType::Identifier("TestMessage".into())),
+            tag.extendee()
+        );
+        assert_eq!(
+            "unresolved extension amount, tried foo.bar.v1.amount, foo.bar.amount, foo.amount & \
+             amount",
+            table
+                .resolve_extension("foo.bar.v1", "amount")
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn enum_value_parent() {
+        let table = table();
+        let value = table.lookup("foo.bar.v1.TestMessage.KIND_UNKNOWN").unwrap();
+        assert_eq!("foo.bar.v1.TestMessage", value.scope());
+        assert_eq!("foo.bar.v1.TestMessage.Kind", value.parent());
+    }
+
+    #[test]
+    fn map_entries() {
+        let mut table = SymbolTable::new();
+        table.add_file(
+            "maps.proto",
+            &crate::parse("syntax = \"proto3\"; message Foo { map<int32, Foo> by_id = 1; }")
+                .unwrap(),
+        );
+
+        let entry = table.lookup("Foo.ByIdEntry").unwrap();
+        assert_eq!(SymbolKind::Message, entry.kind());
+        assert_eq!(
+            Some(&ProtoFieldType::Identifier("Foo".into())),
+            table.lookup("Foo.ByIdEntry.value").unwrap().field_type()
+        );
+    }
+
+    #[test]
+    fn replace_file() {
+        let mut table = table();
+        table.add_file(
+            "other.proto",
+            &crate::parse("syntax = \"proto3\"; message Moved {}").unwrap(),
+        );
+
+        assert!(table.lookup("foo.bar.v1.Other").is_none());
+        assert_eq!("other.proto", table.lookup("Moved").unwrap().file());
+        assert_eq!(1, table.definitions("foo.bar").len());
+
+        table.remove_file("foo.proto");
+        assert!(table.lookup("foo").is_none());
+        assert_eq!(1, table.iter().count());
+    }
+}
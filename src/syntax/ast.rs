@@ -0,0 +1,586 @@
+//! A typed view over the concrete syntax tree. Each type wraps a [`SyntaxNode`] of a single
+//! [`SyntaxKind`] & exposes the parts of its production, returning `None` for anything missing
+//! from an incomplete file.
+
+use crate::parser::{scanner::Scanner, tokens::ProtoToken};
+
+use super::{SyntaxKind, SyntaxNode, SyntaxToken};
+
+pub trait AstNode: Sized {
+    fn can_cast(kind: SyntaxKind) -> bool;
+
+    fn cast(node: SyntaxNode) -> Option<Self>;
+
+    fn syntax(&self) -> &SyntaxNode;
+}
+
+macro_rules! ast_nodes {
+    ($($name:ident,)*) => {
+        $(
+            #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+            pub struct $name(SyntaxNode);
+
+            impl AstNode for $name {
+                fn can_cast(kind: SyntaxKind) -> bool {
+                    kind == SyntaxKind::$name
+                }
+
+                fn cast(node: SyntaxNode) -> Option<Self> {
+                    Self::can_cast(node.kind()).then(|| Self(node))
+                }
+
+                fn syntax(&self) -> &SyntaxNode {
+                    &self.0
+                }
+            }
+
+            impl std::fmt::Display for $name {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    write!(f, "{}", self.0)
+                }
+            }
+        )*
+    };
+}
+
+ast_nodes!(
+    File,
+    Syntax,
+    Package,
+    Import,
+    OptionStatement,
+    OptionName,
+    Constant,
+    MessageLiteral,
+    MessageLiteralField,
+    Message,
+    MessageBody,
+    Field,
+    Group,
+    MapField,
+    OneOf,
+    FieldOptions,
+    FieldOption,
+    Reserved,
+    Range,
+    ReservedName,
+    Extensions,
+    Extend,
+    Enum,
+    EnumBody,
+    EnumValue,
+    Service,
+    Rpc,
+    RpcType,
+    Type,
+    FullIdent,
+    Name,
+);
+
+fn child<N: AstNode>(node: &SyntaxNode) -> Option<N> {
+    node.children().find_map(N::cast)
+}
+
+fn children<N: AstNode>(node: &SyntaxNode) -> impl Iterator<Item = N> {
+    node.children().filter_map(N::cast)
+}
+
+fn token(node: &SyntaxNode, kind: SyntaxKind) -> Option<SyntaxToken> {
+    node.tokens().find(|token| token.kind() == kind)
+}
+
+fn token_where(node: &SyntaxNode, f: impl Fn(SyntaxKind) -> bool) -> Option<SyntaxToken> {
+    node.tokens().find(|token| f(token.kind()))
+}
+
+/// The text of the tokens beneath a node with any trivia removed
+fn text_without_trivia(node: &SyntaxNode) -> String {
+    node.descendant_tokens()
+        .filter(|token| !token.kind().is_trivia())
+        .map(|token| token.text().to_owned())
+        .collect()
+}
+
+/// Decodes the value of a string literal token, resolving any escapes
+pub fn string_value(token: &SyntaxToken) -> Option<String> {
+    match Scanner::new(token.text().as_bytes()).next() {
+        Some(ProtoToken::StringLiteral(value)) => Some(value),
+        _ => None,
+    }
+}
+
+/// Decodes the value of an int literal token, handling hex & octal
+pub fn int_value(token: &SyntaxToken) -> Option<u64> {
+    match Scanner::new(token.text().as_bytes()).next() {
+        Some(ProtoToken::IntLiteral(value)) => Some(value),
+        _ => None,
+    }
+}
+
+fn is_label(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::RepeatedKw | SyntaxKind::OptionalKw | SyntaxKind::RequiredKw
+    )
+}
+
+impl File {
+    pub fn syntax_statement(&self) -> Option<Syntax> {
+        child(&self.0)
+    }
+
+    pub fn package(&self) -> Option<Package> {
+        child(&self.0)
+    }
+
+    pub fn imports(&self) -> impl Iterator<Item = Import> {
+        children(&self.0)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = OptionStatement> {
+        children(&self.0)
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = Message> {
+        children(&self.0)
+    }
+
+    pub fn enums(&self) -> impl Iterator<Item = Enum> {
+        children(&self.0)
+    }
+
+    pub fn services(&self) -> impl Iterator<Item = Service> {
+        children(&self.0)
+    }
+
+    pub fn extends(&self) -> impl Iterator<Item = Extend> {
+        children(&self.0)
+    }
+}
+
+impl Syntax {
+    pub fn value(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::StringLiteral)
+    }
+}
+
+impl Package {
+    pub fn name(&self) -> Option<FullIdent> {
+        child(&self.0)
+    }
+}
+
+impl Import {
+    /// The `weak` or `public` keyword if there is one
+    pub fn modifier(&self) -> Option<SyntaxToken> {
+        token_where(&self.0, |kind| {
+            matches!(kind, SyntaxKind::WeakKw | SyntaxKind::PublicKw)
+        })
+    }
+
+    pub fn path(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::StringLiteral)
+    }
+}
+
+impl OptionStatement {
+    pub fn name(&self) -> Option<OptionName> {
+        child(&self.0)
+    }
+
+    pub fn value(&self) -> Option<Constant> {
+        child(&self.0)
+    }
+}
+
+impl OptionName {
+    /// The option name with trivia removed, `(validate.rules).string.uuid`
+    pub fn text(&self) -> String {
+        text_without_trivia(&self.0)
+    }
+}
+
+impl Constant {
+    pub fn message_literal(&self) -> Option<MessageLiteral> {
+        child(&self.0)
+    }
+
+    /// The constant with trivia removed, adjacent strings are left unjoined
+    pub fn text(&self) -> String {
+        text_without_trivia(&self.0)
+    }
+}
+
+impl MessageLiteral {
+    pub fn fields(&self) -> impl Iterator<Item = MessageLiteralField> {
+        children(&self.0)
+    }
+}
+
+impl MessageLiteralField {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+}
+
+impl Message {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<MessageBody> {
+        child(&self.0)
+    }
+}
+
+impl MessageBody {
+    pub fn fields(&self) -> impl Iterator<Item = Field> {
+        children(&self.0)
+    }
+
+    pub fn map_fields(&self) -> impl Iterator<Item = MapField> {
+        children(&self.0)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = Group> {
+        children(&self.0)
+    }
+
+    pub fn oneofs(&self) -> impl Iterator<Item = OneOf> {
+        children(&self.0)
+    }
+
+    pub fn messages(&self) -> impl Iterator<Item = Message> {
+        children(&self.0)
+    }
+
+    pub fn enums(&self) -> impl Iterator<Item = Enum> {
+        children(&self.0)
+    }
+
+    pub fn extends(&self) -> impl Iterator<Item = Extend> {
+        children(&self.0)
+    }
+
+    pub fn extensions(&self) -> impl Iterator<Item = Extensions> {
+        children(&self.0)
+    }
+
+    pub fn reserved(&self) -> impl Iterator<Item = Reserved> {
+        children(&self.0)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = OptionStatement> {
+        children(&self.0)
+    }
+}
+
+impl Field {
+    /// `repeated`, `optional` or `required`
+    pub fn label(&self) -> Option<SyntaxToken> {
+        token_where(&self.0, is_label)
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn number(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::IntLiteral)
+    }
+
+    pub fn options(&self) -> Option<FieldOptions> {
+        child(&self.0)
+    }
+}
+
+impl Group {
+    pub fn label(&self) -> Option<SyntaxToken> {
+        token_where(&self.0, is_label)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn number(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::IntLiteral)
+    }
+
+    pub fn options(&self) -> Option<FieldOptions> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<MessageBody> {
+        child(&self.0)
+    }
+}
+
+impl MapField {
+    pub fn key_type(&self) -> Option<Type> {
+        children(&self.0).next()
+    }
+
+    pub fn value_type(&self) -> Option<Type> {
+        children(&self.0).nth(1)
+    }
+
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn number(&self) -> Option<SyntaxToken> {
+        token(&self.0, SyntaxKind::IntLiteral)
+    }
+
+    pub fn options(&self) -> Option<FieldOptions> {
+        child(&self.0)
+    }
+}
+
+impl OneOf {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = OptionStatement> {
+        children(&self.0)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = Field> {
+        children(&self.0)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = Group> {
+        children(&self.0)
+    }
+}
+
+impl FieldOptions {
+    pub fn options(&self) -> impl Iterator<Item = FieldOption> {
+        children(&self.0)
+    }
+}
+
+impl FieldOption {
+    pub fn name(&self) -> Option<OptionName> {
+        child(&self.0)
+    }
+
+    pub fn value(&self) -> Option<Constant> {
+        child(&self.0)
+    }
+}
+
+impl Reserved {
+    pub fn ranges(&self) -> impl Iterator<Item = Range> {
+        children(&self.0)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = ReservedName> {
+        children(&self.0)
+    }
+}
+
+impl Range {
+    /// The first number of the range, negated if it has a leading `-`
+    pub fn start(&self) -> Option<i64> {
+        self.bounds().next().flatten()
+    }
+
+    /// The last number of the range, `None` when it ends with `max` or isn't a range at all
+    pub fn end(&self) -> Option<i64> {
+        self.bounds().nth(1).flatten()
+    }
+
+    pub fn is_max(&self) -> bool {
+        token(&self.0, SyntaxKind::MaxKw).is_some()
+    }
+
+    fn bounds(&self) -> impl Iterator<Item = Option<i64>> {
+        let mut negative = false;
+        self.0.tokens().filter_map(move |token| match token.kind() {
+            SyntaxKind::Minus => {
+                negative = true;
+                None
+            }
+            SyntaxKind::IntLiteral => {
+                let value = int_value(&token)
+                    .and_then(|value| i64::try_from(value).ok())
+                    .map(|value| if negative { -value } else { value });
+                negative = false;
+                Some(value)
+            }
+            _ => None,
+        })
+    }
+}
+
+impl ReservedName {
+    pub fn value(&self) -> Option<String> {
+        let token = token_where(&self.0, |kind| {
+            matches!(kind, SyntaxKind::StringLiteral | SyntaxKind::Identifier)
+        })?;
+        match token.kind() {
+            SyntaxKind::StringLiteral => string_value(&token),
+            _ => Some(token.text().to_owned()),
+        }
+    }
+}
+
+impl Extensions {
+    pub fn ranges(&self) -> impl Iterator<Item = Range> {
+        children(&self.0)
+    }
+
+    pub fn options(&self) -> Option<FieldOptions> {
+        child(&self.0)
+    }
+}
+
+impl Extend {
+    /// The message being extended
+    pub fn extendee(&self) -> Option<Type> {
+        child(&self.0)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = Field> {
+        children(&self.0)
+    }
+
+    pub fn groups(&self) -> impl Iterator<Item = Group> {
+        children(&self.0)
+    }
+}
+
+impl Enum {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn body(&self) -> Option<EnumBody> {
+        child(&self.0)
+    }
+}
+
+impl EnumBody {
+    pub fn values(&self) -> impl Iterator<Item = EnumValue> {
+        children(&self.0)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = OptionStatement> {
+        children(&self.0)
+    }
+
+    pub fn reserved(&self) -> impl Iterator<Item = Reserved> {
+        children(&self.0)
+    }
+}
+
+impl EnumValue {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn number(&self) -> Option<i64> {
+        let value = i64::try_from(int_value(&token(&self.0, SyntaxKind::IntLiteral)?)?).ok()?;
+        Some(match token(&self.0, SyntaxKind::Minus) {
+            Some(_) => -value,
+            None => value,
+        })
+    }
+
+    pub fn options(&self) -> Option<FieldOptions> {
+        child(&self.0)
+    }
+}
+
+impl Service {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = OptionStatement> {
+        children(&self.0)
+    }
+
+    pub fn rpcs(&self) -> impl Iterator<Item = Rpc> {
+        children(&self.0)
+    }
+}
+
+impl Rpc {
+    pub fn name(&self) -> Option<Name> {
+        child(&self.0)
+    }
+
+    pub fn input(&self) -> Option<RpcType> {
+        children(&self.0).next()
+    }
+
+    pub fn output(&self) -> Option<RpcType> {
+        children(&self.0).nth(1)
+    }
+
+    pub fn options(&self) -> impl Iterator<Item = OptionStatement> {
+        children(&self.0)
+    }
+}
+
+impl RpcType {
+    pub fn is_stream(&self) -> bool {
+        token(&self.0, SyntaxKind::StreamKw).is_some()
+    }
+
+    pub fn ty(&self) -> Option<Type> {
+        child(&self.0)
+    }
+}
+
+impl Type {
+    /// The scalar keyword for built in types such as `int32`
+    pub fn scalar(&self) -> Option<SyntaxToken> {
+        token_where(&self.0, SyntaxKind::is_scalar_type)
+    }
+
+    /// Absolute types start with a `.` & are resolved from the root scope
+    pub fn is_absolute(&self) -> bool {
+        token(&self.0, SyntaxKind::Dot).is_some()
+    }
+
+    pub fn full_ident(&self) -> Option<FullIdent> {
+        child(&self.0)
+    }
+
+    /// The type with trivia removed, `.foo.bar.Baz`
+    pub fn text(&self) -> String {
+        text_without_trivia(&self.0)
+    }
+}
+
+impl FullIdent {
+    /// The identifier with trivia removed, `foo.bar.v1`
+    pub fn text(&self) -> String {
+        text_without_trivia(&self.0)
+    }
+
+    pub fn parts(&self) -> Vec<String> {
+        self.text().split('.').map(str::to_owned).collect()
+    }
+}
+
+impl Name {
+    pub fn token(&self) -> Option<SyntaxToken> {
+        self.0.tokens().find(|token| !token.kind().is_trivia())
+    }
+
+    pub fn text(&self) -> String {
+        self.token()
+            .map(|token| token.text().to_owned())
+            .unwrap_or_default()
+    }
+}
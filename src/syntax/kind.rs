@@ -0,0 +1,232 @@
+use crate::parser::tokens::ProtoToken;
+
+/// The kind of a token or node within the concrete syntax tree. Tokens mirror [`ProtoToken`] with
+/// the addition of whitespace, node kinds each represent one production of the protobuf grammar.
+#[derive(Debug, Clone, Copy, strum::Display, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SyntaxKind {
+    // Trivia
+    Whitespace,
+    Comment,
+
+    // Tokens
+    Identifier,
+    FullIdentifier,
+    StringLiteral,
+    IntLiteral,
+    FloatLiteral,
+    SyntaxKw,
+    PackageKw,
+    OptionKw,
+    ImportKw,
+    MessageKw,
+    ServiceKw,
+    EnumKw,
+    OneOfKw,
+    RepeatedKw,
+    OptionalKw,
+    RequiredKw,
+    GroupKw,
+    RpcKw,
+    StreamKw,
+    ReturnsKw,
+    WeakKw,
+    PublicKw,
+    ReservedKw,
+    ExtendKw,
+    ExtensionsKw,
+    ToKw,
+    MaxKw,
+    InfKw,
+    MapKw,
+    BoolKw,
+    StringKw,
+    BytesKw,
+    FloatKw,
+    DoubleKw,
+    Int32Kw,
+    Int64Kw,
+    Uint32Kw,
+    Uint64Kw,
+    Sint32Kw,
+    Sint64Kw,
+    Fixed32Kw,
+    Fixed64Kw,
+    Sfixed32Kw,
+    Sfixed64Kw,
+    Colon,
+    SemiColon,
+    /// `{`
+    OpenBracket,
+    /// `}`
+    CloseBracket,
+    OpenParen,
+    CloseParen,
+    /// `[`
+    OpenBrace,
+    /// `]`
+    CloseBrace,
+    Equals,
+    LessThan,
+    GreaterThan,
+    Comma,
+    Dot,
+    Minus,
+    Plus,
+    Invalid,
+
+    // Nodes
+    File,
+    /// `syntax = "proto3";`
+    Syntax,
+    /// `package foo.bar;`
+    Package,
+    /// `import public "foo/bar.proto";`
+    Import,
+    /// `option java_package = "com.foo";`
+    OptionStatement,
+    /// `(validate.rules).string.uuid`
+    OptionName,
+    /// A scalar, string, identifier or aggregate option value
+    Constant,
+    /// The text format body of an aggregate option value, `{ get: "/v1/foo" }`
+    MessageLiteral,
+    /// `get: "/v1/foo"` within a message literal
+    MessageLiteralField,
+    /// `[1, 2, 3]` within a message literal
+    ListLiteral,
+    /// `message Foo { ... }`
+    Message,
+    /// The braces and declarations of a message or group
+    MessageBody,
+    /// `repeated string foo = 1 [deprecated = true];`
+    Field,
+    /// `repeated group Foo = 1 { ... }`
+    Group,
+    /// `map<string, Foo> foo = 1;`
+    MapField,
+    /// `oneof foo { ... }`
+    OneOf,
+    /// `[deprecated = true, json_name = "foo"]`
+    FieldOptions,
+    /// `deprecated = true` within field options
+    FieldOption,
+    /// `reserved 1, 5 to 10;` or `reserved "foo";`
+    Reserved,
+    /// `5 to 10` within reserved or extensions statements
+    Range,
+    /// `"foo"` within a reserved statement
+    ReservedName,
+    /// `extensions 100 to max;`
+    Extensions,
+    /// `extend Foo { ... }`
+    Extend,
+    /// `enum Foo { ... }`
+    Enum,
+    /// The braces and values of an enum
+    EnumBody,
+    /// `FOO_UNKNOWN = 0;`
+    EnumValue,
+    /// `service Foo { ... }`
+    Service,
+    /// `rpc Foo(FooRequest) returns (stream FooResponse);`
+    Rpc,
+    /// `(stream FooRequest)` within an rpc
+    RpcType,
+    /// A scalar type or a reference to a message or enum, `.foo.bar.Baz`
+    Type,
+    /// A dotted name such as a package, `foo.bar.v1`
+    FullIdent,
+    /// The name of a declaration
+    Name,
+    /// A lone `;`
+    EmptyStatement,
+    /// Tokens which couldn't be parsed
+    Error,
+}
+
+impl SyntaxKind {
+    pub fn is_trivia(self) -> bool {
+        matches!(self, Self::Whitespace | Self::Comment)
+    }
+
+    /// Keywords are contextual in protobuf, so any of them can also be used as a name
+    pub fn is_keyword(self) -> bool {
+        (Self::SyntaxKw..=Self::Sfixed64Kw).contains(&self)
+    }
+
+    pub fn is_scalar_type(self) -> bool {
+        (Self::BoolKw..=Self::Sfixed64Kw).contains(&self)
+    }
+
+    pub fn is_token(self) -> bool {
+        self <= Self::Invalid
+    }
+}
+
+impl From<&ProtoToken> for SyntaxKind {
+    fn from(token: &ProtoToken) -> Self {
+        match token {
+            ProtoToken::FullIdentifier(_) => Self::FullIdentifier,
+            ProtoToken::Identifier(_) => Self::Identifier,
+            ProtoToken::StringLiteral(_) | ProtoToken::BytesLiteral(_) => Self::StringLiteral,
+            ProtoToken::IntLiteral(_) => Self::IntLiteral,
+            ProtoToken::FloatLiteral(_) => Self::FloatLiteral,
+            ProtoToken::Bool => Self::BoolKw,
+            ProtoToken::String => Self::StringKw,
+            ProtoToken::Bytes => Self::BytesKw,
+            ProtoToken::Float => Self::FloatKw,
+            ProtoToken::Double => Self::DoubleKw,
+            ProtoToken::Int32 => Self::Int32Kw,
+            ProtoToken::Int64 => Self::Int64Kw,
+            ProtoToken::Uint32 => Self::Uint32Kw,
+            ProtoToken::Uint64 => Self::Uint64Kw,
+            ProtoToken::Sint32 => Self::Sint32Kw,
+            ProtoToken::Sint64 => Self::Sint64Kw,
+            ProtoToken::Fixed32 => Self::Fixed32Kw,
+            ProtoToken::Fixed64 => Self::Fixed64Kw,
+            ProtoToken::Sfixed32 => Self::Sfixed32Kw,
+            ProtoToken::Sfixed64 => Self::Sfixed64Kw,
+            ProtoToken::Colon => Self::Colon,
+            ProtoToken::SemiColon => Self::SemiColon,
+            ProtoToken::Syntax => Self::SyntaxKw,
+            ProtoToken::Package => Self::PackageKw,
+            ProtoToken::Option => Self::OptionKw,
+            ProtoToken::Import => Self::ImportKw,
+            ProtoToken::Message => Self::MessageKw,
+            ProtoToken::Service => Self::ServiceKw,
+            ProtoToken::Enum => Self::EnumKw,
+            ProtoToken::OneOf => Self::OneOfKw,
+            ProtoToken::Repeated => Self::RepeatedKw,
+            ProtoToken::Optional => Self::OptionalKw,
+            ProtoToken::Required => Self::RequiredKw,
+            ProtoToken::Group => Self::GroupKw,
+            ProtoToken::Rpc => Self::RpcKw,
+            ProtoToken::Stream => Self::StreamKw,
+            ProtoToken::Returns => Self::ReturnsKw,
+            ProtoToken::OpenBrace => Self::OpenBrace,
+            ProtoToken::CloseBrace => Self::CloseBrace,
+            ProtoToken::OpenParen => Self::OpenParen,
+            ProtoToken::CloseParen => Self::CloseParen,
+            ProtoToken::OpenBracket => Self::OpenBracket,
+            ProtoToken::CloseBracket => Self::CloseBracket,
+            ProtoToken::Equals => Self::Equals,
+            ProtoToken::Weak => Self::WeakKw,
+            ProtoToken::Public => Self::PublicKw,
+            ProtoToken::Reserved => Self::ReservedKw,
+            ProtoToken::Extend => Self::ExtendKw,
+            ProtoToken::Extensions => Self::ExtensionsKw,
+            ProtoToken::To => Self::ToKw,
+            ProtoToken::Max => Self::MaxKw,
+            ProtoToken::Inf => Self::InfKw,
+            ProtoToken::Map => Self::MapKw,
+            ProtoToken::LessThan => Self::LessThan,
+            ProtoToken::GreaterThan => Self::GreaterThan,
+            ProtoToken::Comma => Self::Comma,
+            ProtoToken::Dot => Self::Dot,
+            ProtoToken::Minus => Self::Minus,
+            ProtoToken::Plus => Self::Plus,
+            ProtoToken::Comment(_) => Self::Comment,
+            ProtoToken::Invalid => Self::Invalid,
+        }
+    }
+}
@@ -0,0 +1,238 @@
+//! A lossless concrete syntax tree for proto files. Unlike [`crate::ast::ProtoFile`] every
+//! token, comment & whitespace is kept, so the tree prints back to exactly the source it was
+//! parsed from & edits can be made to a single node without reprinting the whole file.
+//!
+//! The tree is split into two layers, immutable [`GreenNode`]s which only know their kind, width &
+//! children, with [`SyntaxNode`]s layered on top to give parent pointers & offsets. The typed
+//! [`ast`] module then wraps syntax nodes to give access to each part of a declaration.
+
+pub mod ast;
+mod green;
+mod kind;
+mod parser;
+mod red;
+
+use std::sync::Arc;
+
+use crate::diagnostics::Diagnostic;
+
+pub use green::{GreenElement, GreenNode, GreenToken};
+pub use kind::SyntaxKind;
+pub use red::{SyntaxElement, SyntaxNode, SyntaxToken};
+
+use ast::AstNode;
+
+/// The result of parsing a file into a syntax tree. A tree is always produced, anything which
+/// couldn't be parsed is wrapped in [`SyntaxKind::Error`] nodes with a matching diagnostic.
+#[derive(Debug, Clone)]
+pub struct Parse {
+    green: Arc<GreenNode>,
+    errors: Vec<Diagnostic>,
+}
+
+impl Parse {
+    pub fn green(&self) -> &Arc<GreenNode> {
+        &self.green
+    }
+
+    pub fn syntax(&self) -> SyntaxNode {
+        SyntaxNode::new_root(self.green.clone())
+    }
+
+    pub fn file(&self) -> ast::File {
+        ast::File::cast(self.syntax()).expect("root is always a file")
+    }
+
+    pub fn errors(&self) -> &[Diagnostic] {
+        &self.errors
+    }
+}
+
+pub fn parse(text: &str) -> Parse {
+    let (green, errors) = parser::parse(text);
+    Parse {
+        green: Arc::new(green),
+        errors,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"// Leading comment
+syntax = "proto3";
+
+package foo.bar.v1;
+
+import public "foo/bar/baz.proto";
+
+option go_package = "github.com/ch629/proto_ls/foo/bar/v1;bar";
+option (custom.http) = { get: "/v1/foo" additional_bindings { post: "/v1/bar" } };
+
+/* Documents TestMessage */
+message TestMessage {
+    reserved 4, 8 to max;
+    reserved "old";
+    string name = 1;
+    .foo.bar.baz.Amount amount = 2 [deprecated = true, (validate.rules).string.uuid = true];
+    map<string, foo.baz.Result> testing = 3;
+    oneof choice {
+        int32 a = 5;
+        bytes b = 6;
+    }
+    enum Kind {
+        option allow_alias = true;
+        KIND_UNKNOWN = 0;
+        KIND_OTHER = -1;
+    }
+    extensions 100 to 200;
+    optional group Legacy = 7 {
+        uint64 id = 1;
+    }
+}
+
+extend google.protobuf.FieldOptions {
+    string tag = 5000;
+}
+
+service TestService {
+    rpc Get(stream GetRequest) returns (GetResponse);
+    rpc List(ListRequest) returns (stream ListResponse) {
+        option deprecated = true;
+    }
+}
+"#;
+
+    #[test]
+    fn round_trip() {
+        let parse = parse(SOURCE);
+        assert_eq!(Vec::<Diagnostic>::new(), parse.errors());
+        assert_eq!(SOURCE, parse.syntax().text());
+    }
+
+    #[test]
+    fn round_trip_with_errors() {
+        let source = "syntax = ; message Foo { string = 1; int32 b = 2; } £ enum {";
+        let parse = parse(source);
+
+        assert_eq!(source, parse.syntax().text());
+        assert_eq!(
+            vec![
+                "1:10: error: expected StringLiteral but got SemiColon",
+                "1:33: error: expected a name but got Equals",
+                "1:53: error: expected a top level declaration but got Invalid",
+                "1:61: error: expected a name but got OpenBracket",
+            ],
+            parse
+                .errors()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        // Parsing continues after errors
+        let message = parse.file().messages().next().unwrap();
+        let field = message.body().unwrap().fields().nth(1).unwrap();
+        assert_eq!("b", field.name().unwrap().text());
+    }
+
+    #[test]
+    fn typed_view() {
+        let file = parse(SOURCE).file();
+
+        assert_eq!(
+            "\"proto3\"",
+            file.syntax_statement().unwrap().value().unwrap().text()
+        );
+        assert_eq!("foo.bar.v1", file.package().unwrap().name().unwrap().text());
+        let import = file.imports().next().unwrap();
+        assert_eq!(SyntaxKind::PublicKw, import.modifier().unwrap().kind());
+        assert_eq!(
+            Some("foo/bar/baz.proto".to_owned()),
+            ast::string_value(&import.path().unwrap())
+        );
+
+        let body = file.messages().next().unwrap().body().unwrap();
+        let amount = body.fields().nth(1).unwrap();
+        assert_eq!(".foo.bar.baz.Amount", amount.ty().unwrap().text());
+        assert!(amount.ty().unwrap().is_absolute());
+        assert_eq!(
+            vec!["deprecated", "(validate.rules).string.uuid"],
+            amount
+                .options()
+                .unwrap()
+                .options()
+                .map(|opt| opt.name().unwrap().text())
+                .collect::<Vec<_>>()
+        );
+        let map = body.map_fields().next().unwrap();
+        assert_eq!("string", map.key_type().unwrap().text());
+        assert_eq!("foo.baz.Result", map.value_type().unwrap().text());
+        assert_eq!(2, body.oneofs().next().unwrap().fields().count());
+
+        let ranges: Vec<_> = body.reserved().next().unwrap().ranges().collect();
+        assert_eq!((Some(4), None), (ranges[0].start(), ranges[0].end()));
+        assert!(ranges[1].is_max());
+
+        let values: Vec<_> = body
+            .enums()
+            .next()
+            .unwrap()
+            .body()
+            .unwrap()
+            .values()
+            .map(|value| (value.name().unwrap().text(), value.number().unwrap()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("KIND_UNKNOWN".to_owned(), 0),
+                ("KIND_OTHER".to_owned(), -1)
+            ],
+            values
+        );
+
+        let rpcs: Vec<_> = file.services().next().unwrap().rpcs().collect();
+        assert!(rpcs[0].input().unwrap().is_stream());
+        assert!(rpcs[1].output().unwrap().is_stream());
+        assert_eq!(1, rpcs[1].options().count());
+    }
+
+    #[test]
+    fn leading_comments_belong_to_the_parent() {
+        let file = parse(SOURCE).file();
+        let message = file.messages().next().unwrap();
+
+        assert!(message.syntax().text().starts_with("message TestMessage"));
+        assert!(file
+            .syntax()
+            .tokens()
+            .any(|token| token.text() == "/* Documents TestMessage */"));
+    }
+
+    #[test]
+    fn replace_node() {
+        let parse = parse(SOURCE);
+        let field = parse
+            .file()
+            .messages()
+            .next()
+            .unwrap()
+            .body()
+            .unwrap()
+            .fields()
+            .next()
+            .unwrap();
+        let name = field.name().unwrap();
+
+        let new_name = GreenNode::new(
+            SyntaxKind::Name,
+            vec![GreenToken::new(SyntaxKind::Identifier, "title").into()],
+        );
+        let root = name.syntax().replace_with(new_name);
+
+        assert_eq!(
+            SOURCE.replace("string name = 1;", "string title = 1;"),
+            root.to_string()
+        );
+    }
+}
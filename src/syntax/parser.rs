@@ -0,0 +1,707 @@
+use crate::{
+    diagnostics::Diagnostic,
+    parser::scanner::Scanner,
+    span::{Position, Span},
+};
+
+use super::{
+    green::{GreenNode, GreenNodeBuilder},
+    kind::SyntaxKind::{self, *},
+};
+
+struct Token<'t> {
+    kind: SyntaxKind,
+    text: &'t str,
+    span: Span,
+}
+
+/// Splits the source into tokens using the [`Scanner`], the text between the scanned tokens is
+/// kept as whitespace so that no input is lost.
+fn lex(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut scan = Scanner::new(text.as_bytes());
+    let mut end = Position::default();
+    while let Some(token) = scan.next() {
+        let span = scan.last_span();
+        if end != span.start() {
+            tokens.push(Token {
+                kind: Whitespace,
+                text: &text[end.offset()..span.start().offset()],
+                span: Span::new(end, span.start()),
+            });
+        }
+        tokens.push(Token {
+            kind: SyntaxKind::from(&token),
+            text: &text[span.start().offset()..span.end().offset()],
+            span,
+        });
+        end = span.end();
+    }
+
+    // Trailing whitespace is skipped by the scanner before it finds the EOF
+    let eof = scan.position();
+    if end != eof {
+        tokens.push(Token {
+            kind: Whitespace,
+            text: &text[end.offset()..eof.offset()],
+            span: Span::new(end, eof),
+        });
+    }
+    tokens
+}
+
+pub(crate) fn parse(text: &str) -> (GreenNode, Vec<Diagnostic>) {
+    let mut p = Parser {
+        tokens: lex(text),
+        pos: 0,
+        builder: GreenNodeBuilder::default(),
+        errors: vec![],
+    };
+    file(&mut p);
+    (p.builder.finish(), p.errors)
+}
+
+struct Parser<'t> {
+    tokens: Vec<Token<'t>>,
+    pos: usize,
+    builder: GreenNodeBuilder,
+    errors: Vec<Diagnostic>,
+}
+
+// Each parse function either returns None after recording an error, leaving the enclosing
+// statement to recover, or Some once it has parsed its whole production.
+type Parsed = Option<()>;
+
+impl<'t> Parser<'t> {
+    /// The kind of the nth token ahead, skipping any trivia
+    fn nth(&self, n: usize) -> Option<SyntaxKind> {
+        self.tokens[self.pos..]
+            .iter()
+            .map(|token| token.kind)
+            .filter(|kind| !kind.is_trivia())
+            .nth(n)
+    }
+
+    fn current(&self) -> Option<SyntaxKind> {
+        self.nth(0)
+    }
+
+    fn at(&self, kind: SyntaxKind) -> bool {
+        self.current() == Some(kind)
+    }
+
+    fn at_eof(&self) -> bool {
+        self.current().is_none()
+    }
+
+    /// Names can be identifiers or any keyword, `message message {}` is valid protobuf
+    fn at_name(&self) -> bool {
+        self.current()
+            .is_some_and(|kind| kind == Identifier || kind.is_keyword())
+    }
+
+    fn bump_trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.pos) {
+            if !token.kind.is_trivia() {
+                break;
+            }
+            self.builder.token(token.kind, token.text);
+            self.pos += 1;
+        }
+    }
+
+    fn bump(&mut self) {
+        self.bump_trivia();
+        if let Some(token) = self.tokens.get(self.pos) {
+            self.builder.token(token.kind, token.text);
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, kind: SyntaxKind) -> bool {
+        if self.at(kind) {
+            self.bump();
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, kind: SyntaxKind) -> Parsed {
+        if self.eat(kind) {
+            return Some(());
+        }
+        self.error(format!("expected {kind}"))
+    }
+
+    // Trivia before a node belongs to the parent, so leading comments sit alongside the
+    // declaration they document rather than inside it
+    fn start(&mut self, kind: SyntaxKind) {
+        self.bump_trivia();
+        self.builder.start_node(kind);
+    }
+
+    fn finish(&mut self) {
+        self.builder.finish_node();
+    }
+
+    fn node(&mut self, kind: SyntaxKind, f: impl FnOnce(&mut Self) -> Parsed) -> Parsed {
+        self.start(kind);
+        let parsed = f(self);
+        self.finish();
+        parsed
+    }
+
+    /// Parses a statement, skipping everything up to the end of it if it fails to parse
+    fn statement(&mut self, kind: SyntaxKind, f: impl FnOnce(&mut Self) -> Parsed) {
+        self.start(kind);
+        if f(self).is_none() {
+            self.recover();
+        }
+        self.finish();
+    }
+
+    fn current_span(&self) -> Span {
+        match self.tokens[self.pos..]
+            .iter()
+            .find(|token| !token.kind.is_trivia())
+        {
+            Some(token) => token.span,
+            None => {
+                let end = self.tokens.last().map(|t| t.span.end()).unwrap_or_default();
+                Span::new(end, end)
+            }
+        }
+    }
+
+    fn error(&mut self, message: impl Into<String>) -> Parsed {
+        let message = match self.current() {
+            Some(kind) => format!("{} but got {kind}", message.into()),
+            None => format!("{} but received EOF", message.into()),
+        };
+        self.errors
+            .push(Diagnostic::error(self.current_span(), message));
+        None
+    }
+
+    /// Records an error & wraps the current token in an error node so parsing can continue
+    fn error_bump(&mut self, message: impl Into<String>) {
+        self.error(message);
+        self.start(Error);
+        self.bump();
+        self.finish();
+    }
+
+    // recover skips to the end of the current statement, stepping over any nested blocks. The
+    // closing `}` of the enclosing block is left for the caller.
+    fn recover(&mut self) {
+        self.start(Error);
+        let mut depth = 0;
+        while let Some(kind) = self.current() {
+            match kind {
+                SemiColon if depth == 0 => {
+                    self.bump();
+                    break;
+                }
+                CloseBracket if depth == 0 => break,
+                CloseBracket => depth -= 1,
+                OpenBracket => depth += 1,
+                _ => {}
+            }
+            self.bump();
+            if kind == CloseBracket && depth == 0 {
+                break;
+            }
+        }
+        self.finish();
+    }
+
+    /// Parses `{ ... }`, calling `item` for each declaration between the braces
+    fn block(&mut self, mut item: impl FnMut(&mut Self)) -> Parsed {
+        self.expect(OpenBracket)?;
+        while !self.at(CloseBracket) && !self.at_eof() {
+            if self.at(SemiColon) {
+                self.node(EmptyStatement, |p| p.expect(SemiColon));
+                continue;
+            }
+            item(self);
+        }
+        self.expect(CloseBracket)
+    }
+}
+
+// proto = [syntax] { import | package | option | topLevelDef | emptyStatement }
+fn file(p: &mut Parser) {
+    // Leading trivia has no parent other than the file itself
+    p.builder.start_node(File);
+    while let Some(kind) = p.current() {
+        match kind {
+            SyntaxKw => p.statement(Syntax, syntax),
+            ImportKw => p.statement(Import, import),
+            PackageKw => p.statement(Package, package),
+            OptionKw => p.statement(OptionStatement, option),
+            MessageKw => p.statement(Message, message),
+            EnumKw => p.statement(Enum, enumeration),
+            ServiceKw => p.statement(Service, service),
+            ExtendKw => p.statement(Extend, extend),
+            SemiColon => p.statement(EmptyStatement, |p| p.expect(SemiColon)),
+            _ => p.error_bump("expected a top level declaration"),
+        }
+    }
+    p.bump_trivia();
+    p.finish();
+}
+
+// syntax = "syntax" "=" strLit ";"
+fn syntax(p: &mut Parser) -> Parsed {
+    p.expect(SyntaxKw)?;
+    p.expect(Equals)?;
+    p.expect(StringLiteral)?;
+    p.expect(SemiColon)
+}
+
+// import = "import" [ "weak" | "public" ] strLit ";"
+fn import(p: &mut Parser) -> Parsed {
+    p.expect(ImportKw)?;
+    if !p.eat(WeakKw) {
+        p.eat(PublicKw);
+    }
+    p.expect(StringLiteral)?;
+    p.expect(SemiColon)
+}
+
+// package = "package" fullIdent ";"
+fn package(p: &mut Parser) -> Parsed {
+    p.expect(PackageKw)?;
+    full_ident(p)?;
+    p.expect(SemiColon)
+}
+
+// option = "option" optionName "=" constant ";"
+fn option(p: &mut Parser) -> Parsed {
+    p.expect(OptionKw)?;
+    option_name(p)?;
+    p.expect(Equals)?;
+    constant(p)?;
+    p.expect(SemiColon)
+}
+
+// optionName = ( ident | "(" fullIdent ")" ) { "." ( ident | "(" fullIdent ")" ) }
+fn option_name(p: &mut Parser) -> Parsed {
+    p.node(OptionName, |p| loop {
+        if p.eat(OpenParen) {
+            p.eat(Dot);
+            full_ident(p)?;
+            p.expect(CloseParen)?;
+        } else if p.at_name() || p.at(FullIdentifier) {
+            p.bump();
+        } else {
+            return p.error("expected an option name");
+        }
+
+        if !p.eat(Dot) {
+            return Some(());
+        }
+    })
+}
+
+// fullIdent = ident { "." ident }
+fn full_ident(p: &mut Parser) -> Parsed {
+    p.node(FullIdent, |p| {
+        loop {
+            if !(p.at_name() || p.at(FullIdentifier)) {
+                return p.error("expected an identifier");
+            }
+            p.bump();
+            // The scanner joins dotted names into a single token, this only continues when there
+            // is trivia around the dot
+            if !p.eat(Dot) {
+                break;
+            }
+        }
+        Some(())
+    })
+}
+
+// type = scalar | [ "." ] fullIdent
+fn ty(p: &mut Parser) -> Parsed {
+    p.node(Type, |p| match p.current() {
+        Some(kind) if kind.is_scalar_type() && p.nth(1) != Some(Dot) => {
+            p.bump();
+            Some(())
+        }
+        _ => {
+            p.eat(Dot);
+            full_ident(p)
+        }
+    })
+}
+
+fn name(p: &mut Parser) -> Parsed {
+    if !p.at_name() {
+        return p.error("expected a name");
+    }
+    p.node(Name, |p| {
+        p.bump();
+        Some(())
+    })
+}
+
+// constant = fullIdent | ( [ "-" | "+" ] intLit ) | ( [ "-" | "+" ] floatLit ) | strLit | boolLit |
+//            messageLiteral
+fn constant(p: &mut Parser) -> Parsed {
+    p.node(Constant, |p| match p.current() {
+        Some(OpenBracket) => message_literal(p),
+        Some(Minus | Plus) => {
+            p.bump();
+            match p.current() {
+                Some(IntLiteral | FloatLiteral | InfKw | Identifier) => {
+                    p.bump();
+                    Some(())
+                }
+                _ => p.error("expected a number"),
+            }
+        }
+        Some(StringLiteral) => {
+            // Adjacent strings are concatenated
+            while p.eat(StringLiteral) {}
+            Some(())
+        }
+        Some(IntLiteral | FloatLiteral) => {
+            p.bump();
+            Some(())
+        }
+        Some(FullIdentifier | Identifier | Dot) => {
+            p.eat(Dot);
+            full_ident(p)
+        }
+        Some(kind) if kind.is_keyword() => full_ident(p),
+        _ => p.error("expected a constant"),
+    })
+}
+
+// messageLiteral = "{" { messageLiteralField } "}", `<` and `>` can be used in place of braces
+fn message_literal(p: &mut Parser) -> Parsed {
+    p.node(MessageLiteral, |p| {
+        let close = if p.eat(LessThan) {
+            GreaterThan
+        } else {
+            p.expect(OpenBracket)?;
+            CloseBracket
+        };
+        while !p.at(close) && !p.at_eof() {
+            message_literal_field(p)?;
+        }
+        p.expect(close)
+    })
+}
+
+// messageLiteralField = ( name | "[" typeName "]" ) [ ":" ] value [ "," | ";" ]
+fn message_literal_field(p: &mut Parser) -> Parsed {
+    p.node(MessageLiteralField, |p| {
+        if p.eat(OpenBrace) {
+            // Extension names or Any type URLs, `[type.googleapis.com/foo.Bar]`
+            while !p.at(CloseBrace) && !p.at_eof() {
+                p.bump();
+            }
+            p.expect(CloseBrace)?;
+        } else if p.at_name() {
+            p.node(Name, |p| {
+                p.bump();
+                Some(())
+            })?;
+        } else {
+            return p.error("expected a field name");
+        }
+
+        if p.eat(Colon) {
+            literal_value(p)?;
+        } else if p.at(OpenBracket) || p.at(LessThan) {
+            message_literal(p)?;
+        } else {
+            return p.error("expected Colon");
+        }
+
+        if !p.eat(Comma) {
+            p.eat(SemiColon);
+        }
+        Some(())
+    })
+}
+
+fn literal_value(p: &mut Parser) -> Parsed {
+    match p.current() {
+        Some(OpenBracket | LessThan) => message_literal(p),
+        Some(OpenBrace) => p.node(ListLiteral, |p| {
+            p.expect(OpenBrace)?;
+            while !p.at(CloseBrace) && !p.at_eof() {
+                literal_value(p)?;
+                if !p.eat(Comma) {
+                    break;
+                }
+            }
+            p.expect(CloseBrace)
+        }),
+        _ => constant(p),
+    }
+}
+
+// message = "message" messageName messageBody
+fn message(p: &mut Parser) -> Parsed {
+    p.expect(MessageKw)?;
+    name(p)?;
+    message_body(p)
+}
+
+// messageBody = "{" { field | enum | message | extend | extensions | group | option | oneof |
+//               mapField | reserved | emptyStatement } "}"
+fn message_body(p: &mut Parser) -> Parsed {
+    p.node(MessageBody, |p| p.block(message_item))
+}
+
+fn message_item(p: &mut Parser) {
+    match p.current() {
+        Some(OptionKw) => p.statement(OptionStatement, option),
+        Some(MessageKw) => p.statement(Message, message),
+        Some(EnumKw) => p.statement(Enum, enumeration),
+        Some(ExtendKw) => p.statement(Extend, extend),
+        Some(ExtensionsKw) => p.statement(Extensions, extensions),
+        Some(ReservedKw) => p.statement(Reserved, reserved),
+        Some(OneOfKw) => p.statement(OneOf, oneof),
+        Some(MapKw) if p.nth(1) == Some(LessThan) => p.statement(MapField, map_field),
+        _ if at_group(p) => p.statement(Group, group),
+        Some(RepeatedKw | OptionalKw | RequiredKw | Dot | FullIdentifier) => {
+            p.statement(Field, field)
+        }
+        _ if p.at_name() => p.statement(Field, field),
+        _ => p.error_bump("expected a message field or declaration"),
+    }
+}
+
+fn at_group(p: &Parser) -> bool {
+    match p.current() {
+        Some(RepeatedKw | OptionalKw | RequiredKw) => p.nth(1) == Some(GroupKw),
+        Some(GroupKw) => p.nth(1) != Some(Dot) && p.nth(2) == Some(Equals),
+        _ => false,
+    }
+}
+
+fn label(p: &mut Parser) {
+    if matches!(p.current(), Some(RepeatedKw | OptionalKw | RequiredKw)) {
+        p.bump();
+    }
+}
+
+// field = [ label ] type fieldName "=" fieldNumber [ "[" fieldOptions "]" ] ";"
+fn field(p: &mut Parser) -> Parsed {
+    label(p);
+    ty(p)?;
+    name(p)?;
+    p.expect(Equals)?;
+    p.expect(IntLiteral)?;
+    if p.at(OpenBrace) {
+        field_options(p)?;
+    }
+    p.expect(SemiColon)
+}
+
+// group = label "group" groupName "=" fieldNumber [ "[" fieldOptions "]" ] messageBody
+fn group(p: &mut Parser) -> Parsed {
+    label(p);
+    p.expect(GroupKw)?;
+    name(p)?;
+    p.expect(Equals)?;
+    p.expect(IntLiteral)?;
+    if p.at(OpenBrace) {
+        field_options(p)?;
+    }
+    message_body(p)
+}
+
+// mapField = "map" "<" keyType "," type ">" mapName "=" fieldNumber [ "[" fieldOptions "]" ] ";"
+fn map_field(p: &mut Parser) -> Parsed {
+    p.expect(MapKw)?;
+    p.expect(LessThan)?;
+    ty(p)?;
+    p.expect(Comma)?;
+    ty(p)?;
+    p.expect(GreaterThan)?;
+    name(p)?;
+    p.expect(Equals)?;
+    p.expect(IntLiteral)?;
+    if p.at(OpenBrace) {
+        field_options(p)?;
+    }
+    p.expect(SemiColon)
+}
+
+// fieldOptions = fieldOption { ","  fieldOption }
+fn field_options(p: &mut Parser) -> Parsed {
+    p.node(FieldOptions, |p| {
+        p.expect(OpenBrace)?;
+        loop {
+            p.node(FieldOption, |p| {
+                option_name(p)?;
+                p.expect(Equals)?;
+                constant(p)
+            })?;
+            if !p.eat(Comma) {
+                break;
+            }
+        }
+        p.expect(CloseBrace)
+    })
+}
+
+// oneof = "oneof" oneofName "{" { option | oneofField | group | emptyStatement } "}"
+fn oneof(p: &mut Parser) -> Parsed {
+    p.expect(OneOfKw)?;
+    name(p)?;
+    p.block(|p| match p.current() {
+        Some(OptionKw) => p.statement(OptionStatement, option),
+        _ if at_group(p) => p.statement(Group, group),
+        Some(MapKw) if p.nth(1) == Some(LessThan) => p.statement(MapField, map_field),
+        Some(RepeatedKw | OptionalKw | RequiredKw | Dot | FullIdentifier) => {
+            p.statement(Field, field)
+        }
+        _ if p.at_name() => p.statement(Field, field),
+        _ => p.error_bump("expected a oneof field"),
+    })
+}
+
+// reserved = "reserved" ( ranges | strFieldNames ) ";"
+fn reserved(p: &mut Parser) -> Parsed {
+    p.expect(ReservedKw)?;
+    if p.at(StringLiteral) || p.at(Identifier) {
+        loop {
+            p.node(ReservedName, |p| {
+                p.bump();
+                Some(())
+            });
+            if !p.eat(Comma) {
+                break;
+            }
+            if !(p.at(StringLiteral) || p.at(Identifier)) {
+                return p.error("expected a field name");
+            }
+        }
+    } else {
+        ranges(p)?;
+    }
+    p.expect(SemiColon)
+}
+
+// ranges = range { "," range }
+fn ranges(p: &mut Parser) -> Parsed {
+    loop {
+        range(p)?;
+        if !p.eat(Comma) {
+            return Some(());
+        }
+    }
+}
+
+// range =  intLit [ "to" ( intLit | "max" ) ], enum ranges can be negative
+fn range(p: &mut Parser) -> Parsed {
+    p.node(Range, |p| {
+        p.eat(Minus);
+        p.expect(IntLiteral)?;
+        if p.eat(ToKw) && !p.eat(MaxKw) {
+            p.eat(Minus);
+            p.expect(IntLiteral)?;
+        }
+        Some(())
+    })
+}
+
+// extensions = "extensions" ranges [ "[" fieldOptions "]" ] ";"
+fn extensions(p: &mut Parser) -> Parsed {
+    p.expect(ExtensionsKw)?;
+    ranges(p)?;
+    if p.at(OpenBrace) {
+        field_options(p)?;
+    }
+    p.expect(SemiColon)
+}
+
+// extend = "extend" messageType "{" { field | group | emptyStatement } "}"
+fn extend(p: &mut Parser) -> Parsed {
+    p.expect(ExtendKw)?;
+    ty(p)?;
+    p.block(|p| {
+        if at_group(p) {
+            p.statement(Group, group)
+        } else if p.at_name() || matches!(p.current(), Some(Dot | FullIdentifier)) {
+            p.statement(Field, field)
+        } else {
+            p.error_bump("expected an extension field")
+        }
+    })
+}
+
+// enum = "enum" enumName enumBody
+fn enumeration(p: &mut Parser) -> Parsed {
+    p.expect(EnumKw)?;
+    name(p)?;
+    p.node(EnumBody, |p| {
+        // enumBody = "{" { option | enumField | emptyStatement | reserved } "}"
+        p.block(|p| match p.current() {
+            Some(OptionKw) => p.statement(OptionStatement, option),
+            Some(ReservedKw) => p.statement(Reserved, reserved),
+            _ if p.at_name() => p.statement(EnumValue, enum_value),
+            _ => p.error_bump("expected an enum value"),
+        })
+    })
+}
+
+// enumField = ident "=" [ "-" ] intLit [ "[" enumValueOption { ","  enumValueOption } "]" ] ";"
+fn enum_value(p: &mut Parser) -> Parsed {
+    name(p)?;
+    p.expect(Equals)?;
+    p.eat(Minus);
+    p.expect(IntLiteral)?;
+    if p.at(OpenBrace) {
+        field_options(p)?;
+    }
+    p.expect(SemiColon)
+}
+
+// service = "service" serviceName "{" { option | rpc | emptyStatement } "}"
+fn service(p: &mut Parser) -> Parsed {
+    p.expect(ServiceKw)?;
+    name(p)?;
+    p.block(|p| match p.current() {
+        Some(OptionKw) => p.statement(OptionStatement, option),
+        Some(RpcKw) => p.statement(Rpc, rpc),
+        _ => p.error_bump("expected an rpc"),
+    })
+}
+
+// rpc = "rpc" rpcName "(" [ "stream" ] messageType ")" "returns" "(" [ "stream" ]
+//       messageType ")" (( "{" {option | emptyStatement } "}" ) | ";")
+fn rpc(p: &mut Parser) -> Parsed {
+    p.expect(RpcKw)?;
+    name(p)?;
+    rpc_type(p)?;
+    p.expect(ReturnsKw)?;
+    rpc_type(p)?;
+    if p.at(OpenBracket) {
+        p.block(|p| match p.current() {
+            Some(OptionKw) => p.statement(OptionStatement, option),
+            _ => p.error_bump("expected an option"),
+        })
+    } else {
+        p.expect(SemiColon)
+    }
+}
+
+fn rpc_type(p: &mut Parser) -> Parsed {
+    p.node(RpcType, |p| {
+        p.expect(OpenParen)?;
+        // `stream` is only a keyword when followed by the message type
+        if p.at(StreamKw) && p.nth(1) != Some(CloseParen) {
+            p.bump();
+        }
+        ty(p)?;
+        p.expect(CloseParen)
+    })
+}
@@ -0,0 +1,209 @@
+//! Warns about uses of declarations marked `deprecated = true` by field types, rpcs, extends &
+//! option values, so they can be found before the declarations are removed. Uses within deprecated
+//! declarations aren't reported.
+
+use crate::ast::{MessageField, ProtoExtend, ProtoFieldType, ProtoFile, ProtoRpc};
+use crate::diagnostics::{Diagnostic, Tag};
+use crate::span::Span;
+use crate::symbols::{Symbol, VisibleSymbols};
+use crate::visit::{self, Context, Visitor};
+
+use super::resolved;
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = DeprecatedCheck {
+        symbols,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+/// A warning for using `symbol` at `span` when it's deprecated
+pub(super) fn deprecated_use(span: Span, symbol: &Symbol) -> Option<Diagnostic> {
+    symbol.deprecated().then(|| {
+        Diagnostic::warning(span, format!("{} is deprecated", symbol.full_name()))
+            .with_tag(Tag::Deprecated)
+    })
+}
+
+/// Whether the declaration called `full_name` or one enclosing it is deprecated, uses within
+/// deprecated declarations aren't reported
+pub(super) fn within_deprecated(symbols: &VisibleSymbols<'_>, full_name: &str) -> bool {
+    full_name
+        .match_indices('.')
+        .map(|(i, _)| &full_name[..i])
+        .chain([full_name])
+        .any(|name| symbols.lookup(name).is_some_and(Symbol::deprecated))
+}
+
+struct DeprecatedCheck<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl DeprecatedCheck<'_> {
+    fn field_type(&mut self, ctx: &Context, field_type: &ProtoFieldType, span: Span) {
+        match field_type {
+            ProtoFieldType::Identifier(_) | ProtoFieldType::FullIdentifier(_) => {}
+            ProtoFieldType::Map { value, .. } => return self.field_type(ctx, value, span),
+            _ => return,
+        }
+        if let Some(symbol) = resolved(self.symbols, ctx, field_type) {
+            self.diagnostics.extend(deprecated_use(span, symbol));
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for DeprecatedCheck<'_> {
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        if !within_deprecated(self.symbols, &ctx.full_name(field.name())) {
+            self.field_type(ctx, field.field_type(), field.span());
+        }
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+        let name = format!("{}.{}", ctx.service().unwrap_or_default(), rpc.name());
+        if !within_deprecated(self.symbols, &ctx.full_name(&name)) {
+            self.field_type(ctx, rpc.request().message_type(), rpc.span());
+            self.field_type(ctx, rpc.returns().message_type(), rpc.span());
+        }
+        visit::walk_rpc(self, ctx, rpc);
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        if !within_deprecated(self.symbols, &ctx.scope()) {
+            self.field_type(ctx, extend.extendee(), extend.span());
+        }
+        visit::walk_extend(self, ctx, extend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::diagnostics::Tag;
+    use crate::workspace::Workspace;
+
+    #[test]
+    fn deprecated_uses() {
+        let parsed = crate::parse_file(
+            "test.proto",
+            r#"syntax = "proto3";
+message Old { option deprecated = true; Old self = 1; }
+enum Kind {
+    option deprecated = true;
+    KIND_UNKNOWN = 0;
+}
+message Current {
+    Old old = 1;
+    map<string, Kind> kinds = 2;
+    Old replaced = 3 [deprecated = true];
+}
+service Svc {
+    rpc Get(Old) returns (Current);
+    rpc List(Old) returns (Current) { option deprecated = true; }
+}
+service OldSvc {
+    option deprecated = true;
+    rpc Get(Old) returns (Old);
+}"#,
+        );
+        let diagnostics: Vec<_> = parsed.diagnostics().iter().collect();
+
+        assert_eq!(
+            vec![
+                "8:5: warning: Old is deprecated",
+                "9:5: warning: Kind is deprecated",
+                "13:5: warning: Old is deprecated",
+            ],
+            diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        assert!(diagnostics
+            .iter()
+            .all(|diagnostic| diagnostic.tag() == Some(Tag::Deprecated)));
+    }
+
+    #[test]
+    fn deprecated_extendees() {
+        let parsed = crate::parse_file(
+            "test.proto",
+            r#"syntax = "proto2";
+message Old { option deprecated = true; extensions 100 to 200; }
+extend Old { optional int32 a = 100; }
+message Current {
+    extend Old { optional int32 b = 101; }
+}
+message Older {
+    option deprecated = true;
+    extend Old { optional int32 c = 102; }
+}"#,
+        );
+
+        assert_eq!(
+            vec![
+                "5:5: warning: Old is deprecated",
+                "3:1: warning: Old is deprecated",
+            ],
+            parsed
+                .diagnostics()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn deprecated_option_values() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        workspace.open(
+            "test.proto",
+            r#"syntax = "proto2";
+import "google/protobuf/descriptor.proto";
+enum Level { LEVEL_LOW = 0; LEVEL_OLD = 1 [deprecated = true]; }
+message Rule { optional int32 old = 1 [deprecated = true]; optional int32 min = 2; }
+extend google.protobuf.FieldOptions {
+    optional Level level = 50000;
+    optional Rule rule = 50001;
+    optional bool legacy = 50002 [deprecated = true];
+}
+message Foo {
+    optional int32 a = 1 [(level) = LEVEL_OLD, (rule).old = 1];
+    optional int32 b = 2 [(rule) = { min: 1 old: 2 }, (legacy) = true];
+    optional Level c = 3 [default = LEVEL_OLD];
+    optional Level d = 4 [deprecated = true, default = LEVEL_OLD, (legacy) = true];
+}
+message Old {
+    option deprecated = true;
+    option (message_level) = LEVEL_OLD;
+    optional int32 a = 1 [(level) = LEVEL_OLD, (rule) = { old: 1 }];
+    enum Kind { KIND_UNKNOWN = 0 [(value_level) = LEVEL_OLD]; }
+}
+extend google.protobuf.MessageOptions { optional Level message_level = 50000; }
+extend google.protobuf.EnumValueOptions { optional Level value_level = 50000; }"#,
+        );
+
+        assert_eq!(
+            vec![
+                "13:27: warning: LEVEL_OLD is deprecated",
+                "11:27: warning: LEVEL_OLD is deprecated",
+                "11:48: warning: Rule.old is deprecated",
+                "12:27: warning: Rule.old is deprecated",
+                "12:55: warning: legacy is deprecated",
+            ],
+            workspace
+                .diagnostics("test.proto")
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,300 @@
+//! Checks that the declarations within a scope don't share a name, that the fields of a message
+//! don't share a number, & that extensions of the same message don't share a number
+
+use std::collections::HashMap;
+
+use crate::ast::{MessageField, ProtoEnum, ProtoExtend, ProtoFile, ProtoMessage, ProtoService};
+use crate::diagnostics::Diagnostic;
+use crate::span::Span;
+use crate::symbols::VisibleSymbols;
+use crate::visit::{self, Context, Visitor};
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = DuplicateCheck {
+        symbols,
+        extensions: HashMap::new(),
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct DuplicateCheck<'a, 'ast> {
+    symbols: &'a VisibleSymbols<'a>,
+    // The extensions declared for each message by the full name of the message
+    extensions: HashMap<String, Vec<&'ast MessageField>>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+// A name declared within a scope, with what kind of declaration it is
+type Declaration<'ast> = (&'ast str, &'static str, Span);
+
+// field_declarations lists the names fields declare, groups declare a message too
+fn field_declarations<'ast>(
+    fields: impl IntoIterator<Item = &'ast MessageField>,
+) -> impl Iterator<Item = Declaration<'ast>> {
+    fields.into_iter().flat_map(|field| {
+        let group = field
+            .group()
+            .map(|group| (group.name(), "message", field.span()));
+        [(field.name(), "field", field.span())]
+            .into_iter()
+            .chain(group)
+    })
+}
+
+// type_declarations lists the names of messages & enums, enum values are siblings of their enum
+fn type_declarations<'ast>(
+    messages: &'ast [ProtoMessage],
+    enums: &'ast [ProtoEnum],
+) -> impl Iterator<Item = Declaration<'ast>> {
+    let messages = messages
+        .iter()
+        .map(|message| (message.name(), "message", message.span()));
+    let enums = enums.iter().flat_map(|enumeration| {
+        let values = enumeration
+            .values()
+            .iter()
+            .map(|value| (value.name(), "enum value", value.span()));
+        [(enumeration.name(), "enum", enumeration.span())]
+            .into_iter()
+            .chain(values)
+    });
+    messages.chain(enums)
+}
+
+impl DuplicateCheck<'_, '_> {
+    // names reports declarations sharing a name within a scope, like protoc they can't share one
+    // even when they're different kinds of declaration
+    fn names<'ast>(&mut self, declarations: impl IntoIterator<Item = Declaration<'ast>>) {
+        let mut declarations: Vec<_> = declarations.into_iter().collect();
+        declarations.sort_by_key(|(_, _, span)| span.start().offset());
+        let mut seen: HashMap<&str, (&str, Span)> = HashMap::new();
+        for (name, kind, span) in declarations {
+            let Some(&(first_kind, first)) = seen.get(name) else {
+                seen.insert(name, (kind, span));
+                continue;
+            };
+            // Enum values sharing a name are reported by the enum checks
+            if kind == "enum value" && first_kind == "enum value" {
+                continue;
+            }
+            let message = if kind == first_kind {
+                format!(
+                    "duplicate {kind} name {name}, first declared at {}",
+                    first.start()
+                )
+            } else {
+                format!(
+                    "{kind} {name} has the same name as the {first_kind} declared at {}, names \
+                     must be unique within their scope",
+                    first.start()
+                )
+            };
+            self.diagnostics.push(
+                Diagnostic::error(span, message)
+                    .with_related(first, format!("{name} is first declared here")),
+            );
+        }
+    }
+
+    fn numbers<'ast>(&mut self, fields: impl IntoIterator<Item = &'ast MessageField>) {
+        let mut seen: HashMap<u32, &MessageField> = HashMap::new();
+        for field in fields {
+            match seen.get(&field.index()) {
+                Some(first) => self.diagnostics.push(
+                    Diagnostic::error(
+                        field.span(),
+                        format!(
+                            "field number {} is already used by {} at {}",
+                            field.index(),
+                            first.name(),
+                            first.span().start()
+                        ),
+                    )
+                    .with_related(
+                        first.span(),
+                        format!("{} uses {} here", first.name(), field.index()),
+                    ),
+                ),
+                None => {
+                    seen.insert(field.index(), field);
+                }
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for DuplicateCheck<'_, 'ast> {
+    fn visit_file(&mut self, ctx: &mut Context, file: &'ast ProtoFile) {
+        visit::walk_file(self, ctx, file);
+        // Extensions declared at the top level share the file's scope
+        let services = file
+            .services()
+            .iter()
+            .map(|service| (service.name(), "service", service.span()));
+        self.names(
+            type_declarations(file.messages(), file.enums())
+                .chain(services)
+                .chain(field_declarations(
+                    file.extends().iter().flat_map(ProtoExtend::fields),
+                )),
+        );
+
+        let mut extensions: Vec<_> = std::mem::take(&mut self.extensions).into_values().collect();
+        extensions.sort_by_key(|fields| fields.first().map(|field| field.span().start().offset()));
+        for fields in extensions {
+            self.numbers(fields);
+        }
+    }
+
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        let fields = || {
+            message
+                .fields()
+                .iter()
+                .chain(message.oneofs().iter().flat_map(|oneof| oneof.fields()))
+        };
+        // Extensions declared within a message share its scope but not its numbers
+        let extensions = message.extends().iter().flat_map(ProtoExtend::fields);
+        let oneofs = message
+            .oneofs()
+            .iter()
+            .map(|oneof| (oneof.name(), "oneof", oneof.span()));
+        self.names(
+            field_declarations(fields().chain(extensions))
+                .chain(oneofs)
+                .chain(type_declarations(message.messages(), message.enums())),
+        );
+        self.numbers(fields());
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_service(&mut self, ctx: &mut Context, service: &'ast ProtoService) {
+        self.names(
+            service
+                .rpcs()
+                .iter()
+                .map(|rpc| (rpc.name(), "rpc", rpc.span())),
+        );
+        visit::walk_service(self, ctx, service);
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        // Numbers are unique per extended message, however its name is written
+        let extendee = extend.extendee().name();
+        let extendee = match self.symbols.resolve_type(&ctx.scope(), &extendee) {
+            Ok(symbol) => symbol.full_name().to_owned(),
+            Err(_) => extendee,
+        };
+        self.extensions
+            .entry(extendee)
+            .or_default()
+            .extend(extend.fields());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::related;
+
+    #[test]
+    fn duplicate_fields() {
+        assert_eq!(
+            vec![
+                "4:5: error: duplicate field name a, first declared at 3:5 (3:5: a is first \
+                 declared here)",
+                "8:9: error: duplicate field name b, first declared at 5:5 (5:5: b is first \
+                 declared here)",
+                "10:18: error: duplicate field name c, first declared at 7:9 (7:9: c is first \
+                 declared here)",
+                "5:5: error: field number 1 is already used by a at 3:5 (3:5: a uses 1 here)",
+                "7:9: error: field number 2 is already used by a at 4:5 (4:5: a uses 2 here)",
+                "14:15: error: duplicate field name d, first declared at 13:14 (13:14: d is \
+                 first declared here)",
+                "13:14: error: field number 100 is already used by c at 10:18 (10:18: c uses \
+                 100 here)",
+            ],
+            related(
+                r#"syntax = "proto2";
+message Foo {
+    optional int32 a = 1;
+    optional int32 a = 2;
+    optional int32 b = 1;
+    oneof choice {
+        int32 c = 2;
+        int32 b = 3;
+    }
+    extend Foo { optional int32 c = 100; }
+    extensions 100 to 200;
+}
+extend Foo { optional int32 d = 100; }
+extend .Foo { optional int32 d = 101; }
+extend Foo {}"#
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_names_in_messages() {
+        assert_eq!(
+            vec![
+                "4:5: error: field Foo has the same name as the message declared at 3:5, names \
+                 must be unique within their scope (3:5: Foo is first declared here)",
+                "6:5: error: field Kind has the same name as the enum declared at 5:5, names \
+                 must be unique within their scope (5:5: Kind is first declared here)",
+                "7:5: error: field KIND_UNKNOWN has the same name as the enum value declared at \
+                 5:17, names must be unique within their scope (5:17: KIND_UNKNOWN is first \
+                 declared here)",
+                "9:5: error: message Bar has the same name as the oneof declared at 8:5, names \
+                 must be unique within their scope (8:5: Bar is first declared here)",
+                "11:5: error: duplicate message name Result, first declared at 10:5 (10:5: \
+                 Result is first declared here)",
+            ],
+            related(
+                r#"syntax = "proto2";
+message M {
+    message Foo {}
+    optional Foo Foo = 1;
+    enum Kind { KIND_UNKNOWN = 0; }
+    optional int32 Kind = 2;
+    optional int32 KIND_UNKNOWN = 3;
+    oneof Bar { int32 b = 4; }
+    message Bar {}
+    repeated group Result = 5 {}
+    message Result {}
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_names_in_files() {
+        assert_eq!(
+            vec![
+                "8:5: error: duplicate rpc name Get, first declared at 7:5 (7:5: Get is first \
+                 declared here)",
+                "3:1: error: duplicate message name A, first declared at 2:1 (2:1: A is first \
+                 declared here)",
+                "4:1: error: enum A has the same name as the message declared at 2:1, names \
+                 must be unique within their scope (2:1: A is first declared here)",
+                "5:1: error: service A has the same name as the message declared at 2:1, \
+                 names must be unique within their scope (2:1: A is first declared here)",
+            ],
+            related(
+                r#"syntax = "proto3";
+message A {}
+message A {}
+enum A { X = 0; }
+service A {}
+service S {
+    rpc Get(A) returns (A);
+    rpc Get(A) returns (A);
+}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,168 @@
+//! Checks that enum values don't share a number unless aliases are allowed, & that their names are
+//! unique within the scope they're declared in
+
+use std::collections::HashMap;
+
+use crate::ast::{ProtoConstant, ProtoEnum, ProtoEnumValue, ProtoFile};
+use crate::diagnostics::Diagnostic;
+use crate::visit::{self, Context, Visitor};
+
+pub(super) fn check(file: &ProtoFile, diagnostics: &mut Vec<Diagnostic>) {
+    let mut check = EnumCheck {
+        values: HashMap::new(),
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct EnumCheck<'a, 'ast> {
+    // The first value declared at each full name with the name of its enum. Values are siblings of
+    // their enum, so values of different enums in the same scope share these names.
+    values: HashMap<String, (&'ast str, &'ast ProtoEnumValue)>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'ast> EnumCheck<'_, 'ast> {
+    fn numbers(&mut self, enumeration: &ProtoEnum) {
+        let allow_alias = enumeration
+            .options()
+            .iter()
+            .find(|option| option.name() == "allow_alias");
+        let allowed =
+            allow_alias.is_some_and(|option| matches!(option.value(), ProtoConstant::Bool(true)));
+
+        let mut seen: HashMap<i32, &ProtoEnumValue> = HashMap::new();
+        let mut aliased = false;
+        for value in enumeration.values() {
+            let Some(first) = seen.get(&value.number()) else {
+                seen.insert(value.number(), value);
+                continue;
+            };
+            aliased = true;
+            if allowed {
+                continue;
+            }
+            self.diagnostics.push(
+                Diagnostic::error(
+                    value.span(),
+                    format!(
+                        "enum value number {} is already used by {} at {}, set option \
+                         allow_alias = true to allow aliases",
+                        value.number(),
+                        first.name(),
+                        first.span().start()
+                    ),
+                )
+                .with_related(
+                    first.span(),
+                    format!("{} uses {} here", first.name(), value.number()),
+                ),
+            );
+        }
+
+        if let Some(option) = allow_alias.filter(|_| allowed && !aliased) {
+            self.diagnostics.push(Diagnostic::error(
+                option.span(),
+                format!(
+                    "allow_alias is set but no values of {} share a number",
+                    enumeration.name()
+                ),
+            ));
+        }
+    }
+
+    fn names(&mut self, ctx: &Context, enumeration: &'ast ProtoEnum) {
+        for value in enumeration.values() {
+            let full_name = ctx.full_name(value.name());
+            let Some(&(first_enum, first)) = self.values.get(&full_name) else {
+                self.values.insert(full_name, (enumeration.name(), value));
+                continue;
+            };
+            let message = if first_enum == enumeration.name() {
+                format!(
+                    "duplicate enum value name {}, first declared at {}",
+                    value.name(),
+                    first.span().start()
+                )
+            } else {
+                let scope = ctx.scope();
+                let scope = if scope.is_empty() {
+                    "the root scope".to_owned()
+                } else {
+                    scope
+                };
+                format!(
+                    "enum value {} is already declared by {first_enum} at {}, enum values are \
+                     siblings of their enum so they must be unique within {scope}",
+                    value.name(),
+                    first.span().start()
+                )
+            };
+            self.diagnostics
+                .push(Diagnostic::error(value.span(), message).with_related(
+                    first.span(),
+                    format!("{} is first declared here", first.name()),
+                ));
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for EnumCheck<'_, 'ast> {
+    fn visit_enum(&mut self, ctx: &mut Context, enumeration: &'ast ProtoEnum) {
+        self.numbers(enumeration);
+        self.names(ctx, enumeration);
+        visit::walk_enum(self, ctx, enumeration);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::related;
+
+    #[test]
+    fn aliases() {
+        assert_eq!(
+            vec![
+                "5:5: error: enum value number 1 is already used by KIND_A at 4:5, set option \
+                 allow_alias = true to allow aliases (4:5: KIND_A uses 1 here)",
+                "13:5: error: allow_alias is set but no values of Unaliased share a number",
+            ],
+            related(
+                r#"syntax = "proto3";
+enum Kind {
+    KIND_UNKNOWN = 0;
+    KIND_A = 1;
+    KIND_B = 1;
+}
+enum Aliased {
+    option allow_alias = true;
+    ALIASED_UNKNOWN = 0;
+    ALIASED_DEFAULT = 0;
+}
+enum Unaliased {
+    option allow_alias = true;
+    UNALIASED_UNKNOWN = 0;
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn sibling_values() {
+        assert_eq!(
+            vec![
+                "3:25: error: enum value UNKNOWN is already declared by Kind at 2:13, enum values \
+                 are siblings of their enum so they must be unique within foo (2:13: UNKNOWN is \
+                 first declared here)",
+                "3:38: error: duplicate enum value name OTHER, first declared at 3:14 (3:14: OTHER \
+                 is first declared here)",
+            ],
+            related(
+                r#"syntax = "proto3"; package foo;
+enum Kind { UNKNOWN = 0; }
+enum Other { OTHER = 0; UNKNOWN = 1; OTHER = 2; }
+message Foo { enum Kind { UNKNOWN = 0; } }"#
+            )
+        );
+    }
+}
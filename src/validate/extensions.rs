@@ -0,0 +1,97 @@
+//! Checks that extensions use numbers within the extension ranges of the message they extend
+
+use crate::ast::{ProtoExtend, ProtoFile};
+use crate::diagnostics::Diagnostic;
+use crate::symbols::{SymbolKind, VisibleSymbols};
+use crate::visit::{self, Context, Visitor};
+
+use super::resolved;
+
+use super::join;
+use super::numbers::describe;
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = ExtensionCheck {
+        symbols,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct ExtensionCheck<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'ast> Visitor<'ast> for ExtensionCheck<'_> {
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        let extendee = match resolved(self.symbols, ctx, extend.extendee()) {
+            Some(symbol) if symbol.kind() == SymbolKind::Message => symbol,
+            _ => return,
+        };
+
+        let ranges = extendee.extension_ranges();
+        if ranges.is_empty() {
+            self.diagnostics.push(Diagnostic::error(
+                extend.span(),
+                format!(
+                    "{} can't be extended as it doesn't declare any extension ranges",
+                    extendee.full_name()
+                ),
+            ));
+            return;
+        }
+        for field in extend.fields() {
+            let number = i64::from(field.index());
+            if ranges.iter().any(|range| range.contains(number)) {
+                continue;
+            }
+            let described: Vec<_> = ranges.iter().map(describe).collect();
+            let described: Vec<_> = described.iter().map(String::as_str).collect();
+            self.diagnostics.push(Diagnostic::error(
+                field.span(),
+                format!(
+                    "extension number {number} isn't within an extension range of {}, which \
+                     declares {}",
+                    extendee.full_name(),
+                    join(&described)
+                ),
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::errors;
+
+    #[test]
+    fn extension_ranges() {
+        assert_eq!(
+            vec![
+                "8:5: error: extension number 150 isn't within an extension range of foo.Foo, \
+                 which declares 100 to 120 & 1000 to max",
+                "12:1: error: foo.Bar can't be extended as it doesn't declare any extension \
+                 ranges",
+            ],
+            errors(
+                r#"syntax = "proto2";
+package foo;
+message Foo {
+    extensions 100 to 120, 1000 to max;
+}
+message Bar {}
+extend Foo {
+    optional int32 a = 150;
+    optional int32 b = 120;
+    optional int32 c = 536870911;
+}
+extend Bar { optional int32 d = 1; }"#
+            )
+        );
+    }
+}
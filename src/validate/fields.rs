@@ -0,0 +1,429 @@
+//! Checks on the `default` & `json_name` pseudo-options of fields, that the JSON names of a
+//! message's fields don't conflict & that oneof fields aren't labelled
+
+use std::collections::HashMap;
+
+use crate::ast::{
+    to_json_name, MessageField, ProtoConstant, ProtoDefault, ProtoFieldLabel, ProtoFieldType,
+    ProtoFile, ProtoMessage, ProtoOption, ProtoSyntax,
+};
+use crate::diagnostics::Diagnostic;
+use crate::symbols::{SymbolKind, VisibleSymbols};
+use crate::visit::{self, Context, Visitor};
+
+use super::deprecated::{deprecated_use, within_deprecated};
+use super::resolved;
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = FieldCheck {
+        syntax: *file.syntax(),
+        symbols,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct FieldCheck<'a> {
+    syntax: ProtoSyntax,
+    symbols: &'a VisibleSymbols<'a>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl FieldCheck<'_> {
+    fn default(&mut self, ctx: &Context, field: &MessageField) {
+        let Some(default) = field.default() else {
+            return;
+        };
+        let span = default.span();
+
+        // proto3 defaults are reported by the proto3 checks
+        if self.syntax == ProtoSyntax::Proto3 {
+            return;
+        }
+        if field.label() == Some(ProtoFieldLabel::Repeated)
+            || matches!(field.field_type(), ProtoFieldType::Map { .. })
+        {
+            self.diagnostics.push(Diagnostic::error(
+                span,
+                "repeated fields can't have default values",
+            ));
+            return;
+        }
+
+        let value = match ProtoDefault::new(field.field_type(), default.value()) {
+            Ok(value) => value,
+            Err(message) => {
+                self.diagnostics.push(Diagnostic::error(span, message));
+                return;
+            }
+        };
+        let ProtoDefault::Enum(name) = value else {
+            return;
+        };
+        let Some(symbol) = resolved(self.symbols, ctx, field.field_type()) else {
+            return;
+        };
+        if symbol.kind() == SymbolKind::Message {
+            self.diagnostics.push(Diagnostic::error(
+                span,
+                "message fields can't have default values",
+            ));
+            return;
+        }
+        // Enum values are declared alongside their enum
+        let found = self.symbols.children(symbol.scope()).find(|value| {
+            value.kind() == SymbolKind::EnumValue
+                && value.parent() == symbol.full_name()
+                && value.name() == name
+        });
+        if let Some(found) = found {
+            if !within_deprecated(self.symbols, &ctx.full_name(field.name())) {
+                self.diagnostics.extend(deprecated_use(span, found));
+            }
+        } else {
+            self.diagnostics.push(Diagnostic::error(
+                span,
+                format!(
+                    "enum {} has no value named {name}",
+                    field.field_type().name()
+                ),
+            ));
+        }
+    }
+
+    fn json_name(&mut self, ctx: &Context, field: &MessageField) {
+        let Some(json_name) = field.json_name_option() else {
+            return;
+        };
+
+        if ctx.in_extend() {
+            self.diagnostics.push(Diagnostic::error(
+                json_name.span(),
+                "json_name isn't allowed on extension fields",
+            ));
+        } else if !matches!(json_name.value(), ProtoConstant::String(_)) {
+            self.diagnostics.push(Diagnostic::error(
+                json_name.span(),
+                "json_name must be a string",
+            ));
+        }
+    }
+}
+
+impl FieldCheck<'_> {
+    // json_names reports fields with the same JSON name like protoc. The names derived from the
+    // field names are compared first, then the names used including those set with `json_name`.
+    // Like protoc, messages opting in to `deprecated_legacy_json_field_conflicts` only have the
+    // default names compared & only in proto3.
+    fn json_names(&mut self, message: &ProtoMessage) {
+        let legacy = message.options().iter().any(|option| {
+            option.name() == "deprecated_legacy_json_field_conflicts"
+                && *option.value() == ProtoConstant::Bool(true)
+        });
+        if legacy && self.syntax == ProtoSyntax::Proto2 {
+            return;
+        }
+        let fields: Vec<_> = message
+            .fields()
+            .iter()
+            .chain(message.oneofs().iter().flat_map(|oneof| oneof.fields()))
+            .collect();
+        self.json_name_conflicts(&fields, false);
+        if !legacy {
+            self.json_name_conflicts(&fields, true);
+        }
+    }
+
+    fn json_name_conflicts(&mut self, fields: &[&MessageField], use_custom: bool) {
+        let is_custom = |field: &MessageField| {
+            use_custom
+                && matches!(
+                    field.json_name_option().map(ProtoOption::value),
+                    Some(ProtoConstant::String(_))
+                )
+        };
+        let describe = |custom| if custom { "custom" } else { "default" };
+
+        let mut seen: HashMap<String, &MessageField> = HashMap::new();
+        for &field in fields {
+            let name = if use_custom {
+                field.json_name()
+            } else {
+                to_json_name(field.name())
+            };
+            let Some(&first) = seen.get(&name) else {
+                seen.insert(name, field);
+                continue;
+            };
+            let (custom, first_custom) = (is_custom(field), is_custom(first));
+            // Fields with the same name are reported as duplicates, & the first pass has already
+            // compared the default names
+            if field.name() == first.name() || (use_custom && !custom && !first_custom) {
+                continue;
+            }
+
+            let mut message = format!(
+                "the {} JSON name of {} is {name}, the same as the {} JSON name of {} at {}",
+                describe(custom),
+                field.name(),
+                describe(first_custom),
+                first.name(),
+                first.span().start()
+            );
+            // Conflicts with default names are allowed in proto2 as they used to be unchecked
+            let diagnostic = match (custom && first_custom, self.syntax) {
+                (false, ProtoSyntax::Proto2) => Diagnostic::warning(field.span(), message),
+                (false, _) => {
+                    message.push_str(", which isn't allowed in proto3");
+                    Diagnostic::error(field.span(), message)
+                }
+                (true, _) => Diagnostic::error(field.span(), message),
+            };
+            self.diagnostics.push(
+                diagnostic.with_related(first.span(), format!("{} is declared here", first.name())),
+            );
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for FieldCheck<'_> {
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        self.json_names(message);
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        // Labelled maps are reported by the map checks
+        let is_map = matches!(field.field_type(), ProtoFieldType::Map { .. });
+        if let (Some(label), Some(oneof), false) = (field.label(), ctx.oneof(), is_map) {
+            self.diagnostics.push(Diagnostic::error(
+                field.span(),
+                format!(
+                    "fields in a oneof can't be labelled {label}, remove it from {} in {oneof}",
+                    field.name()
+                ),
+            ));
+        }
+        self.default(ctx, field);
+        self.json_name(ctx, field);
+        // The first of each is taken out of the options, so any left are set more than once
+        for option in field.options() {
+            if option.name() == "default" || option.name() == "json_name" {
+                self.diagnostics.push(Diagnostic::error(
+                    option.span(),
+                    format!("{} is set more than once", option.name()),
+                ));
+            }
+        }
+        visit::walk_field(self, ctx, field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{MessageField, ProtoDefault};
+    use crate::validate::{errors, related};
+
+    #[test]
+    fn valid_defaults() {
+        let source = r#"syntax = "proto2";
+message Foo {
+    optional int32 a = 1 [default = -2147483648];
+    optional uint64 b = 2 [default = 18446744073709551615];
+    optional double c = 3 [default = -inf];
+    optional float d = 4 [default = 5];
+    optional bool e = 5 [default = true];
+    optional string f = 6 [default = "f"];
+    optional bytes g = 7 [default = "\xff\x00"];
+    optional Kind h = 8 [default = KIND_OTHER];
+    optional Other.Kind i = 9 [default = OTHER_KIND_UNKNOWN];
+    optional string j = 10 [json_name = "jay"];
+    enum Kind {
+        KIND_UNKNOWN = 0;
+        KIND_OTHER = 1;
+    }
+}
+message Other {
+    enum Kind { OTHER_KIND_UNKNOWN = 0; }
+}"#;
+        assert_eq!(Vec::<String>::new(), errors(source));
+
+        let file = crate::parse(source).unwrap();
+        let fields = file.messages()[0].fields();
+        assert_eq!(
+            Some(Ok(ProtoDefault::Uint(u64::MAX))),
+            fields[1].default_value()
+        );
+        assert_eq!(
+            Some(Ok(ProtoDefault::Bytes(vec![0xff, 0]))),
+            fields[6].default_value()
+        );
+        assert_eq!(
+            Some(Ok(ProtoDefault::Enum("KIND_OTHER".into()))),
+            fields[7].default_value()
+        );
+        assert!(fields[9].options().is_empty());
+    }
+
+    #[test]
+    fn invalid_defaults() {
+        assert_eq!(
+            vec![
+                "3:27: error: default value 2147483648 is out of range for int32",
+                "4:28: error: default value -1 is out of range for uint32",
+                "5:28: error: default value for a double field must be a number",
+                "6:26: error: default value for a bool field must be true or false",
+                "7:28: error: default value for a string field must be valid UTF-8",
+                "8:26: error: enum Kind has no value named KIND_MISSING",
+                "9:25: error: message fields can't have default values",
+                "10:27: error: repeated fields can't have default values",
+                "11:40: error: default is set more than once",
+                "12:29: error: default value is out of range for uint64",
+            ],
+            errors(
+                r#"syntax = "proto2";
+message Foo {
+    optional int32 a = 1 [default = 2147483648];
+    optional uint32 b = 2 [default = -1];
+    optional double c = 3 [default = "c"];
+    optional bool d = 4 [default = 1];
+    optional string e = 5 [default = "\xff"];
+    optional Kind f = 6 [default = KIND_MISSING];
+    optional Foo g = 7 [default = NONE];
+    repeated int32 h = 8 [default = 1];
+    optional int32 i = 9 [default = 1, default = 2];
+    optional uint64 j = 10 [default = 18446744073709551616];
+    enum Kind { KIND_UNKNOWN = 0; }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn labelled_oneof_fields() {
+        assert_eq!(
+            vec![
+                "4:9: error: fields in a oneof can't be labelled optional, remove it from a in o",
+                "5:9: error: fields in a oneof can't be labelled repeated, remove it from b in o",
+            ],
+            errors(
+                r#"syntax = "proto2";
+message Foo {
+    oneof o {
+        optional int32 a = 1;
+        repeated int32 b = 2;
+        int32 c = 3;
+    }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn json_names() {
+        let file = crate::parse(
+            r#"syntax = "proto3";
+message Foo {
+    string foo_bar_baz = 1;
+    string _leading__double = 2;
+    string explicit = 3 [json_name = "other"];
+}"#,
+        )
+        .unwrap();
+        let names: Vec<_> = file.messages()[0]
+            .fields()
+            .iter()
+            .map(MessageField::json_name)
+            .collect();
+        assert_eq!(vec!["fooBarBaz", "LeadingDouble", "other"], names);
+
+        assert_eq!(
+            vec![
+                "2:37: error: json_name must be a string",
+                "3:38: error: json_name isn't allowed on extension fields",
+            ],
+            errors(
+                r#"syntax = "proto2";
+message Foo { optional int32 a = 1 [json_name = 1]; extensions 100 to 200; }
+extend Foo { optional int32 b = 100 [json_name = "b"]; }"#
+            )
+        );
+    }
+
+    #[test]
+    fn json_name_related() {
+        assert_eq!(
+            vec![
+                "4:5: error: the default JSON name of fooBar is fooBar, the same as the default \
+                 JSON name of foo_bar at 3:5, which isn't allowed in proto3 (3:5: foo_bar is \
+                 declared here)",
+                "7:5: error: the custom JSON name of other is baz, the same as the custom JSON \
+                 name of explicit at 5:5 (5:5: explicit is declared here)",
+                "6:20: error: the default JSON name of baz is baz, the same as the custom JSON \
+                 name of explicit at 5:5, which isn't allowed in proto3 (5:5: explicit is \
+                 declared here)",
+            ],
+            related(
+                r#"syntax = "proto3";
+message Foo {
+    string foo_bar = 1;
+    string fooBar = 2;
+    string explicit = 3 [json_name = "baz"];
+    oneof choice { string baz = 4; }
+    string other = 5 [json_name = "baz"];
+}"#
+            )
+        );
+        assert_eq!(
+            vec![
+                "4:5: warning: the default JSON name of fooBar is fooBar, the same as the \
+                 default JSON name of foo_bar at 3:5 (3:5: foo_bar is declared here)",
+                "6:5: error: the custom JSON name of c is a, the same as the custom JSON name of \
+                 b at 5:5 (5:5: b is declared here)",
+            ],
+            related(
+                r#"syntax = "proto2";
+message Foo {
+    optional string foo_bar = 1;
+    optional string fooBar = 2;
+    optional string b = 3 [json_name = "a"];
+    optional string c = 4 [json_name = "a"];
+}"#
+            )
+        );
+        assert_eq!(
+            vec![
+                "5:5: error: the default JSON name of fooBar is fooBar, the same as the default \
+                 JSON name of foo_bar at 4:5, which isn't allowed in proto3 (4:5: foo_bar is \
+                 declared here)",
+            ],
+            related(
+                r#"syntax = "proto3";
+message Foo {
+    option deprecated_legacy_json_field_conflicts = true;
+    string foo_bar = 1;
+    string fooBar = 2;
+    string b = 3 [json_name = "a"];
+    string c = 4 [json_name = "a"];
+}"#
+            )
+        );
+        assert_eq!(
+            Vec::<String>::new(),
+            related(
+                r#"syntax = "proto2";
+message Foo {
+    option deprecated_legacy_json_field_conflicts = true;
+    optional string foo_bar = 1;
+    optional string fooBar = 2;
+    optional string b = 3 [json_name = "a"];
+    optional string c = 4 [json_name = "a"];
+}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Finds the files the declarations used by a file are in, so imports none of whose declarations
+//! are used can be reported
+
+use std::collections::HashSet;
+
+use crate::ast::{
+    MessageField, ProtoConstant, ProtoExtend, ProtoFieldType, ProtoFile, ProtoOption, ProtoRpc,
+};
+use crate::symbols::VisibleSymbols;
+use crate::visit::{self, Context, Visitor};
+
+/// The names of the files declaring the types & extensions `file` uses, including those only used
+/// by custom options
+pub(crate) fn used_files(file: &ProtoFile, symbols: &VisibleSymbols<'_>) -> HashSet<String> {
+    let mut references = References {
+        symbols,
+        files: HashSet::new(),
+    };
+    visit::visit(&mut references, file);
+    references.files
+}
+
+struct References<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    files: HashSet<String>,
+}
+
+impl References<'_> {
+    fn field_type(&mut self, ctx: &Context, field_type: &ProtoFieldType) {
+        match field_type {
+            ProtoFieldType::Identifier(_) | ProtoFieldType::FullIdentifier(_) => {
+                if let Ok(symbol) = self.symbols.resolve_type(&ctx.scope(), &field_type.name()) {
+                    self.files.insert(symbol.file().to_owned());
+                }
+            }
+            ProtoFieldType::Map { value, .. } => self.field_type(ctx, value),
+            _ => {}
+        }
+    }
+
+    fn extension(&mut self, ctx: &Context, name: &str) {
+        let name = name
+            .trim_start_matches(['(', '['])
+            .trim_end_matches([')', ']']);
+        if let Ok(symbol) = self.symbols.resolve_extension(&ctx.scope(), name) {
+            self.files.insert(symbol.file().to_owned());
+        }
+    }
+
+    // constant records the extensions named within an aggregate value, `[foo.bar]: 1`
+    fn constant(&mut self, ctx: &Context, value: &ProtoConstant) {
+        match value {
+            ProtoConstant::Aggregate(fields) => {
+                for field in fields {
+                    if field.name().starts_with('[') && !field.name().contains('/') {
+                        self.extension(ctx, field.name());
+                    }
+                    self.constant(ctx, field.value());
+                }
+            }
+            ProtoConstant::List(values) => {
+                for value in values {
+                    self.constant(ctx, value);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for References<'_> {
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        self.field_type(ctx, field.field_type());
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+        self.field_type(ctx, rpc.request().message_type());
+        self.field_type(ctx, rpc.returns().message_type());
+        visit::walk_rpc(self, ctx, rpc);
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        self.field_type(ctx, extend.extendee());
+        visit::walk_extend(self, ctx, extend);
+    }
+
+    fn visit_option(&mut self, ctx: &mut Context, option: &'ast ProtoOption) {
+        // Each part in parentheses is an extension, `(foo.bar).(baz.qux)`
+        let mut rest = option.name();
+        while let Some(start) = rest.find('(') {
+            let Some(end) = rest[start..].find(')') else {
+                break;
+            };
+            self.extension(ctx, &rest[start..start + end + 1]);
+            rest = &rest[start + end + 1..];
+        }
+        self.constant(ctx, option.value());
+    }
+}
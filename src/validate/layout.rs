@@ -0,0 +1,241 @@
+//! Checks that packages agree with where files are & with each other, as style guides & tools
+//! like buf expect. Each is opt in as protoc itself doesn't care.
+
+use crate::ast::{ProtoConstant, ProtoFile, ProtoOption};
+use crate::diagnostics::Diagnostic;
+
+/// The options setting the package code is generated in for a language, which every file in a
+/// package should set the same way
+const LANGUAGE_PACKAGES: [&str; 7] = [
+    "go_package",
+    "java_package",
+    "csharp_namespace",
+    "objc_class_prefix",
+    "php_namespace",
+    "ruby_package",
+    "swift_prefix",
+];
+
+/// Which layout checks to run, none are by default
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutChecks {
+    /// A file's directory relative to its include path matches its package, `foo.bar.v1` is in
+    /// `foo/bar/v1/`
+    pub package_directory: bool,
+    /// Every file in a directory declares the same package
+    pub directory_package: bool,
+    /// Files in the same package set options like `go_package` & `java_package` to the same values
+    pub language_packages: bool,
+}
+
+/// The layout problems of the file called `name` given the other loaded files
+pub(crate) fn check_layout<'a>(
+    name: &str,
+    file: &ProtoFile,
+    others: impl IntoIterator<Item = (&'a str, &'a ProtoFile)> + Clone,
+    checks: LayoutChecks,
+) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let package = file.package().join(".");
+    let dir = directory(name);
+
+    if checks.package_directory {
+        let expected = file.package().join("/");
+        if dir != expected {
+            let message = if package.is_empty() {
+                format!("files without a package should be in the root directory, not {dir}")
+            } else {
+                format!(
+                    "package {package} doesn't match the file's directory {}, files in it should \
+                     be in {expected}",
+                    describe(dir)
+                )
+            };
+            diagnostics.push(Diagnostic::warning(file.package_span(), message));
+        }
+    }
+
+    if checks.directory_package {
+        let differing = others.clone().into_iter().find(|(other, other_file)| {
+            *other != name && directory(other) == dir && other_file.package() != file.package()
+        });
+        if let Some((other, other_file)) = differing {
+            let other_package = describe_package(other_file);
+            diagnostics.push(
+                Diagnostic::warning(
+                    file.package_span(),
+                    format!(
+                        "{} differs from {other_package} declared by {other}, files in the same \
+                         directory should share a package",
+                        describe_package(file)
+                    ),
+                )
+                .with_related_in(
+                    other,
+                    other_file.package_span(),
+                    format!("{other} declares {other_package} here"),
+                ),
+            );
+        }
+    }
+
+    if checks.language_packages && !package.is_empty() {
+        for option in file.options() {
+            let Some(value) = language_package(option) else {
+                continue;
+            };
+            let differing = others.clone().into_iter().find_map(|(other, other_file)| {
+                if other == name || other_file.package() != file.package() {
+                    return None;
+                }
+                other_file
+                    .options()
+                    .iter()
+                    .find(|other_option| {
+                        other_option.name() == option.name()
+                            && language_package(other_option).is_some_and(|other| other != value)
+                    })
+                    .map(|other_option| (other, other_option))
+            });
+            let Some((other, other_option)) = differing else {
+                continue;
+            };
+            let other_value = language_package(other_option).unwrap_or_default();
+            diagnostics.push(
+                Diagnostic::warning(
+                    option.span(),
+                    format!(
+                        "{} is {value:?} but {other} sets it to {other_value:?}, files in package \
+                         {package} should agree",
+                        option.name()
+                    ),
+                )
+                .with_related_in(
+                    other,
+                    other_option.span(),
+                    format!("{other} sets {} here", option.name()),
+                ),
+            );
+        }
+    }
+    diagnostics
+}
+
+// directory is the directory part of a file name, empty for files in the root
+fn directory(name: &str) -> &str {
+    name.rsplit_once('/').map_or("", |(directory, _)| directory)
+}
+
+fn describe(directory: &str) -> &str {
+    if directory.is_empty() {
+        "the root directory"
+    } else {
+        directory
+    }
+}
+
+fn describe_package(file: &ProtoFile) -> String {
+    if file.package().is_empty() {
+        "no package".to_owned()
+    } else {
+        format!("package {}", file.package().join("."))
+    }
+}
+
+// language_package is the value of one of the language package options
+fn language_package(option: &ProtoOption) -> Option<&str> {
+    match option.value() {
+        ProtoConstant::String(value) if LANGUAGE_PACKAGES.contains(&option.name()) => Some(value),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::LayoutChecks;
+    use crate::workspace::Workspace;
+
+    fn workspace() -> Workspace {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        for (name, source) in [
+            (
+                "foo/bar/v1/a.proto",
+                r#"syntax = "proto3"; package foo.bar.v1;
+option go_package = "example.com/foo/bar/v1;barv1";
+option java_package = "com.foo.bar.v1";"#,
+            ),
+            (
+                "foo/bar/v1/b.proto",
+                r#"syntax = "proto3";
+package foo.bar.v1;
+option java_package = "com.foo.bar.v1";
+option go_package = "example.com/foo/bar;bar";"#,
+            ),
+            (
+                "foo/bar/v1/c.proto",
+                r#"syntax = "proto3";
+package foo.bar;"#,
+            ),
+        ] {
+            workspace.open(name, source);
+        }
+        workspace
+    }
+
+    fn warnings(workspace: &Workspace, name: &str) -> Vec<String> {
+        workspace
+            .diagnostics(name)
+            .iter()
+            .map(|diagnostic| match diagnostic.related().first() {
+                Some(related) => format!(
+                    "{diagnostic} ({}:{}: {})",
+                    related.file().unwrap_or_default(),
+                    related.span().start(),
+                    related.message()
+                ),
+                None => diagnostic.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn off_by_default() {
+        let workspace = workspace();
+        assert!(workspace.diagnostics("foo/bar/v1/b.proto").is_empty());
+        assert!(workspace.diagnostics("foo/bar/v1/c.proto").is_empty());
+    }
+
+    #[test]
+    fn layout() {
+        let mut workspace = workspace();
+        workspace.set_layout_checks(LayoutChecks {
+            package_directory: true,
+            directory_package: true,
+            language_packages: true,
+        });
+
+        assert_eq!(
+            vec![
+                "2:1: warning: package foo.bar.v1 differs from package foo.bar declared by \
+                 foo/bar/v1/c.proto, files in the same directory should share a package \
+                 (foo/bar/v1/c.proto:2:1: foo/bar/v1/c.proto declares package foo.bar here)",
+                "4:1: warning: go_package is \"example.com/foo/bar;bar\" but foo/bar/v1/a.proto \
+                 sets it to \"example.com/foo/bar/v1;barv1\", files in package foo.bar.v1 should \
+                 agree (foo/bar/v1/a.proto:2:1: foo/bar/v1/a.proto sets go_package here)",
+            ],
+            warnings(&workspace, "foo/bar/v1/b.proto")
+        );
+        assert_eq!(
+            vec![
+                "2:1: warning: package foo.bar doesn't match the file's directory foo/bar/v1, \
+                 files in it should be in foo/bar",
+                "2:1: warning: package foo.bar differs from package foo.bar.v1 declared by \
+                 foo/bar/v1/a.proto, files in the same directory should share a package \
+                 (foo/bar/v1/a.proto:1:20: foo/bar/v1/a.proto declares package foo.bar.v1 here)",
+            ],
+            warnings(&workspace, "foo/bar/v1/c.proto")
+        );
+    }
+}
@@ -0,0 +1,222 @@
+//! Checks that map fields use valid key & value types & are declared where maps are allowed, & that
+//! the entry messages protoc synthesizes for them don't collide with other names
+
+use std::collections::HashMap;
+
+use crate::ast::{MessageField, ProtoFieldType, ProtoFile, ProtoMessage};
+use crate::diagnostics::Diagnostic;
+use crate::span::Span;
+use crate::symbols::VisibleSymbols;
+use crate::visit::{self, Context, Visitor};
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = MapCheck {
+        symbols,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct MapCheck<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl MapCheck<'_> {
+    fn key(&mut self, ctx: &Context, field: &MessageField, key: &ProtoFieldType) {
+        use ProtoFieldType as T;
+        let described = match key {
+            T::Int32 | T::Int64 | T::Uint32 | T::Uint64 | T::Sint32 | T::Sint64 => return,
+            T::Fixed32 | T::Fixed64 | T::Sfixed32 | T::Sfixed64 | T::Bool | T::String => return,
+            T::Identifier(_) | T::FullIdentifier(_) => {
+                match self.symbols.resolve_type(&ctx.scope(), &key.name()) {
+                    Ok(symbol) => format!("the {} {}", symbol.kind(), symbol.full_name()),
+                    Err(_) => key.name(),
+                }
+            }
+            other => other.name(),
+        };
+        self.diagnostics.push(Diagnostic::error(
+            field.span(),
+            format!("map keys must be integral or string scalars, not {described}"),
+        ));
+    }
+
+    // entries reports map entry messages sharing a name with another declaration in the message
+    fn entries(&mut self, message: &ProtoMessage) {
+        let fields = || {
+            message
+                .fields()
+                .iter()
+                .chain(message.oneofs().iter().flat_map(|oneof| oneof.fields()))
+        };
+        let mut names: HashMap<String, (&str, Span)> = HashMap::new();
+        let declared = fields()
+            .map(|field| (field.name(), "field", field.span()))
+            .chain(
+                message
+                    .oneofs()
+                    .iter()
+                    .map(|oneof| (oneof.name(), "oneof", oneof.span())),
+            )
+            .chain(
+                message
+                    .messages()
+                    .iter()
+                    .map(|nested| (nested.name(), "message", nested.span())),
+            )
+            .chain(
+                fields()
+                    .filter_map(MessageField::group)
+                    .map(|group| (group.name(), "message", group.span())),
+            )
+            .chain(
+                message
+                    .enums()
+                    .iter()
+                    .map(|nested| (nested.name(), "enum", nested.span())),
+            );
+        for (name, kind, span) in declared {
+            names.entry(name.to_owned()).or_insert((kind, span));
+        }
+
+        for field in fields() {
+            let Some(entry) = field.map_entry_name() else {
+                continue;
+            };
+            let Some(&(kind, span)) = names.get(&entry) else {
+                names.insert(entry, ("map entry message", field.span()));
+                continue;
+            };
+            self.diagnostics.push(
+                Diagnostic::error(
+                    field.span(),
+                    format!(
+                        "map field {} declares the nested message {entry}, which conflicts with \
+                         the {kind} {entry} at {}",
+                        field.name(),
+                        span.start()
+                    ),
+                )
+                .with_related(span, format!("{entry} is declared here")),
+            );
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for MapCheck<'_> {
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        self.entries(message);
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        if let ProtoFieldType::Map { key, value } = field.field_type() {
+            self.key(ctx, field, key);
+            if let ProtoFieldType::Map { .. } = value.as_ref() {
+                self.diagnostics.push(Diagnostic::error(
+                    field.span(),
+                    "map values can't be maps, wrap the inner map in a message instead",
+                ));
+            }
+
+            let misplaced = if let Some(label) = field.label() {
+                Some(format!(
+                    "map fields can't be labelled {label}, maps are implicitly repeated"
+                ))
+            } else if let Some(oneof) = ctx.oneof() {
+                Some(format!(
+                    "map fields can't be declared in a oneof, move {} out of {oneof}",
+                    field.name()
+                ))
+            } else if ctx.in_extend() {
+                Some("map fields can't be extensions".to_owned())
+            } else {
+                None
+            };
+            if let Some(message) = misplaced {
+                self.diagnostics
+                    .push(Diagnostic::error(field.span(), message));
+            }
+        }
+        visit::walk_field(self, ctx, field);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::related;
+
+    #[test]
+    fn key_and_value_types() {
+        assert_eq!(
+            vec![
+                "4:5: error: map keys must be integral or string scalars, not float",
+                "5:5: error: map keys must be integral or string scalars, not bytes",
+                "6:5: error: map keys must be integral or string scalars, not the enum Kind",
+                "7:5: error: map keys must be integral or string scalars, not the message Foo",
+                "8:5: error: map values can't be maps, wrap the inner map in a message instead",
+            ],
+            related(
+                r#"syntax = "proto3";
+enum Kind { KIND_UNKNOWN = 0; }
+message Foo {
+    map<float, string> a = 1;
+    map<bytes, string> b = 2;
+    map<Kind, string> c = 3;
+    map<Foo, string> d = 4;
+    map<string, map<string, string>> e = 5;
+    map<sfixed64, Foo> f = 6;
+    map<bool, Kind> g = 7;
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn misplaced_maps() {
+        assert_eq!(
+            vec![
+                "3:5: error: map fields can't be labelled repeated, maps are implicitly repeated",
+                "4:20: error: map fields can't be declared in a oneof, move b out of choice",
+                "7:14: error: map fields can't be extensions",
+            ],
+            related(
+                r#"syntax = "proto2";
+message Foo {
+    repeated map<string, string> a = 1;
+    oneof choice { map<string, string> b = 2; }
+    extensions 100 to 200;
+}
+extend Foo { map<string, string> c = 100; }"#
+            )
+        );
+    }
+
+    #[test]
+    fn entry_collisions() {
+        assert_eq!(
+            vec![
+                "3:5: error: map field values declares the nested message ValuesEntry, which \
+                 conflicts with the message ValuesEntry at 6:5 (6:5: ValuesEntry is declared \
+                 here)",
+                "5:5: error: map field Foo_bar declares the nested message FooBarEntry, which \
+                 conflicts with the map entry message FooBarEntry at 4:5 (4:5: FooBarEntry is \
+                 declared here)",
+            ],
+            related(
+                r#"syntax = "proto3";
+message Foo {
+    map<string, string> values = 1;
+    map<string, string> foo_bar = 2;
+    map<string, string> Foo_bar = 3;
+    message ValuesEntry {}
+}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,86 @@
+//! Checks on a parsed file which go beyond its syntax, such as default values matching the type of
+//! their field.
+
+mod deprecated;
+mod duplicates;
+mod enums;
+mod extensions;
+mod fields;
+mod imports;
+mod layout;
+mod maps;
+mod numbers;
+mod options;
+mod proto3;
+mod types;
+
+use crate::ast::{ProtoFieldType, ProtoFile};
+use crate::diagnostics::Diagnostic;
+use crate::symbols::{Symbol, VisibleSymbols};
+use crate::visit::Context;
+
+pub(crate) use imports::used_files;
+pub(crate) use layout::check_layout;
+pub use layout::LayoutChecks;
+
+/// Runs every check over `file`, resolving the types it uses with the symbols visible to it
+pub fn validate(file: &ProtoFile, symbols: &VisibleSymbols<'_>) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    types::check(file, symbols, &mut diagnostics);
+    duplicates::check(file, symbols, &mut diagnostics);
+    enums::check(file, &mut diagnostics);
+    fields::check(file, symbols, &mut diagnostics);
+    maps::check(file, symbols, &mut diagnostics);
+    numbers::check(file, &mut diagnostics);
+    extensions::check(file, symbols, &mut diagnostics);
+    options::check(file, symbols, &mut diagnostics);
+    proto3::check(file, symbols, &mut diagnostics);
+    deprecated::check(file, symbols, &mut diagnostics);
+    diagnostics
+}
+
+/// Lists names the way diagnostics do, `A, B & C`
+pub(crate) fn join(names: &[&str]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => (*name).to_owned(),
+        [rest @ .., last] => format!("{} & {last}", rest.join(", ")),
+    }
+}
+
+/// Resolves a type used within the current scope, `None` when it doesn't resolve
+pub(crate) fn resolved<'a>(
+    symbols: &VisibleSymbols<'a>,
+    ctx: &Context,
+    field_type: &ProtoFieldType,
+) -> Option<&'a Symbol> {
+    // Unresolved types are reported by the type checks, so the other checks skip them
+    symbols.resolve_type(&ctx.scope(), &field_type.name()).ok()
+}
+
+/// The diagnostics of `source` as they're displayed
+#[cfg(test)]
+pub(crate) fn errors(source: &str) -> Vec<String> {
+    crate::parse_file("test.proto", source)
+        .diagnostics()
+        .iter()
+        .map(ToString::to_string)
+        .collect()
+}
+
+/// The diagnostics of `source` as they're displayed, followed by their first related location
+#[cfg(test)]
+pub(crate) fn related(source: &str) -> Vec<String> {
+    crate::parse_file("test.proto", source)
+        .diagnostics()
+        .iter()
+        .map(|diagnostic| match diagnostic.related().first() {
+            Some(related) => format!(
+                "{diagnostic} ({}: {})",
+                related.span().start(),
+                related.message()
+            ),
+            None => diagnostic.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,291 @@
+//! Checks that field numbers are within protobuf's limits & aren't reserved, that enum values
+//! aren't reserved, & that reserved ranges don't overlap
+
+use std::ops::RangeInclusive;
+
+use crate::ast::{MessageField, ProtoEnum, ProtoFile, ProtoMessage, ProtoRange};
+use crate::diagnostics::Diagnostic;
+use crate::parser::FIELD_NUMBER_MAX;
+use crate::visit::{self, Context, Visitor};
+
+/// The field numbers reserved for the protobuf implementation
+const IMPLEMENTATION_RESERVED: RangeInclusive<i64> = 19_000..=19_999;
+
+pub(super) fn check(file: &ProtoFile, diagnostics: &mut Vec<Diagnostic>) {
+    visit::visit(&mut NumberCheck { diagnostics }, file);
+}
+
+struct NumberCheck<'a> {
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl NumberCheck<'_> {
+    fn reserved_ranges(&mut self, message: &ProtoMessage) {
+        let ranges = message.reserved_ranges();
+        for (i, range) in ranges.iter().enumerate() {
+            if range.start() > range.end() {
+                self.diagnostics.push(Diagnostic::error(
+                    range.span(),
+                    format!("reserved range {} ends before it starts", describe(range)),
+                ));
+                continue;
+            }
+            if range.start() < 1 || range.end() > FIELD_NUMBER_MAX {
+                self.diagnostics.push(Diagnostic::error(
+                    range.span(),
+                    format!(
+                        "reserved range {} is out of range, field numbers must be between 1 & \
+                         {FIELD_NUMBER_MAX}",
+                        describe(range)
+                    ),
+                ));
+            }
+
+            let overlapping = ranges[..i]
+                .iter()
+                .map(|other| (other, "reserved range"))
+                .chain(
+                    message
+                        .extension_ranges()
+                        .iter()
+                        .map(|other| (other, "extension range")),
+                )
+                .find(|(other, _)| other.start() <= range.end() && range.start() <= other.end());
+            if let Some((other, kind)) = overlapping {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        range.span(),
+                        format!(
+                            "reserved range {} overlaps {kind} {}",
+                            describe(range),
+                            describe(other)
+                        ),
+                    )
+                    .with_related(other.span(), format!("{kind} {}", describe(other))),
+                );
+            }
+        }
+    }
+
+    fn reserved_fields<'ast>(
+        &mut self,
+        message: &ProtoMessage,
+        fields: impl Iterator<Item = &'ast MessageField>,
+    ) {
+        for field in fields {
+            let number = i64::from(field.index());
+            if let Some(range) = message
+                .reserved_ranges()
+                .iter()
+                .find(|range| range.contains(number))
+            {
+                self.diagnostics.push(
+                    Diagnostic::error(field.span(), format!("field number {number} is reserved"))
+                        .with_related(
+                            range.span(),
+                            format!("{} is reserved here", describe(range)),
+                        ),
+                );
+            }
+            if let Some(range) = message
+                .extension_ranges()
+                .iter()
+                .find(|range| range.contains(number))
+            {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        field.span(),
+                        format!("field number {number} is reserved for extensions"),
+                    )
+                    .with_related(
+                        range.span(),
+                        format!("{} is reserved for extensions here", describe(range)),
+                    ),
+                );
+            }
+            if message
+                .reserved_names()
+                .iter()
+                .any(|name| name == field.name())
+            {
+                self.diagnostics.push(Diagnostic::error(
+                    field.span(),
+                    format!("field name {} is reserved", field.name()),
+                ));
+            }
+        }
+    }
+
+    fn reserved_values(&mut self, enumeration: &ProtoEnum) {
+        for value in enumeration.values() {
+            let number = i64::from(value.number());
+            if let Some(range) = enumeration
+                .reserved_ranges()
+                .iter()
+                .find(|range| range.contains(number))
+            {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        value.span(),
+                        format!("enum value number {number} is reserved"),
+                    )
+                    .with_related(
+                        range.span(),
+                        format!("{} is reserved here", describe(range)),
+                    ),
+                );
+            }
+            if enumeration
+                .reserved_names()
+                .iter()
+                .any(|name| name == value.name())
+            {
+                self.diagnostics.push(Diagnostic::error(
+                    value.span(),
+                    format!("enum value name {} is reserved", value.name()),
+                ));
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for NumberCheck<'_> {
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        self.reserved_ranges(message);
+        let fields = message
+            .fields()
+            .iter()
+            .chain(message.oneofs().iter().flat_map(|oneof| oneof.fields()));
+        self.reserved_fields(message, fields);
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        let number = i64::from(field.index());
+        if !(1..=FIELD_NUMBER_MAX).contains(&number) {
+            self.diagnostics.push(Diagnostic::error(
+                field.span(),
+                format!(
+                    "field number {number} is out of range, it must be between 1 & \
+                     {FIELD_NUMBER_MAX}"
+                ),
+            ));
+        } else if IMPLEMENTATION_RESERVED.contains(&number) {
+            self.diagnostics.push(Diagnostic::error(
+                field.span(),
+                format!(
+                    "field number {number} is reserved for the protobuf implementation, which \
+                     uses {} to {}",
+                    IMPLEMENTATION_RESERVED.start(),
+                    IMPLEMENTATION_RESERVED.end()
+                ),
+            ));
+        }
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, enumeration: &'ast ProtoEnum) {
+        self.reserved_values(enumeration);
+        visit::walk_enum(self, ctx, enumeration);
+    }
+}
+
+// describe writes a range as it's declared, `4 to 6` or `15 to max`
+pub(super) fn describe(range: &ProtoRange) -> String {
+    match (range.start(), range.end()) {
+        (start, end) if start == end => start.to_string(),
+        (start, FIELD_NUMBER_MAX) => format!("{start} to max"),
+        (start, end) => format!("{start} to {end}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::related;
+
+    #[test]
+    fn field_numbers() {
+        assert_eq!(
+            vec![
+                "3:5: error: field number 0 is out of range, it must be between 1 & 536870911",
+                "4:5: error: field number 536870912 is out of range, it must be between 1 & \
+                 536870911",
+                "5:5: error: field number 19500 is reserved for the protobuf implementation, \
+                 which uses 19000 to 19999",
+            ],
+            related(
+                r#"syntax = "proto3";
+message Foo {
+    int32 a = 0;
+    int32 b = 536870912;
+    int32 c = 19500;
+    int32 d = 536870911;
+    int32 e = 70000;
+}"#
+            )
+        );
+        assert_eq!(
+            "field number 4294967296 is out of range",
+            crate::parse(r#"message Foo { optional int32 a = 4294967296; }"#)
+                .unwrap_err()
+                .message()
+        );
+    }
+
+    #[test]
+    fn reserved() {
+        assert_eq!(
+            vec![
+                "4:14: error: reserved range 9 to 12 overlaps reserved range 2 to 10 (3:14: \
+                 reserved range 2 to 10)",
+                "4:23: error: reserved range 20 to 15 ends before it starts",
+                "5:14: error: reserved range 100 overlaps extension range 100 to max (7:16: \
+                 extension range 100 to max)",
+                "5:19: error: reserved range 0 is out of range, field numbers must be between 1 \
+                 & 536870911",
+                "8:5: error: field number 2 is reserved (3:14: 2 to 10 is reserved here)",
+                "9:5: error: field number 200 is reserved for extensions (7:16: 100 to max is \
+                 reserved for extensions here)",
+                "11:9: error: field name old is reserved",
+            ],
+            related(
+                r#"syntax = "proto2";
+message Foo {
+    reserved 2 to 10, 14;
+    reserved 9 to 12, 20 to 15;
+    reserved 100, 0;
+    reserved "old";
+    extensions 100 to max;
+    optional int32 a = 2;
+    optional int32 b = 200;
+    oneof choice {
+        int32 old = 13;
+    }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn reserved_enum_values() {
+        assert_eq!(
+            vec![
+                "7:5: error: enum value number 2 is reserved (3:14: 2 to 4 is reserved here)",
+                "8:5: error: enum value name OLD is reserved",
+                "9:5: error: enum value number -1 is reserved (4:14: -1 is reserved here)",
+            ],
+            related(
+                r#"syntax = "proto3";
+enum Kind {
+    reserved 2 to 4, 10;
+    reserved -1;
+    reserved "OLD";
+    KIND_UNKNOWN = 0;
+    KIND_TWO = 2;
+    OLD = 5;
+    KIND_NEGATIVE = -1;
+}"#
+            )
+        );
+    }
+}
@@ -0,0 +1,797 @@
+//! Checks that options are declared for where they're set & that their values fit the type of
+//! their field. Built in options like `features.field_presence` resolve to a field of the options
+//! messages in `descriptor.proto` & then through the fields of its type, custom options like
+//! `(validate.rules).string.uuid` resolve to an extension of those messages instead.
+
+use crate::ast::{
+    MessageField, ProtoConstant, ProtoEnum, ProtoEnumValue, ProtoFieldLabel, ProtoFieldType,
+    ProtoFile, ProtoMessage, ProtoOneOf, ProtoOption, ProtoRpc, ProtoService,
+};
+use crate::diagnostics::Diagnostic;
+use crate::span::Span;
+use crate::symbols::{Symbol, SymbolKind, VisibleSymbols};
+use crate::visit::{self, Context, Visitor};
+use crate::well_known;
+
+use super::deprecated::{deprecated_use, within_deprecated};
+use super::join;
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = OptionCheck {
+        symbols,
+        within_deprecated: false,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+/// The kinds of declaration options can be set on, each has its own options message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    File,
+    Message,
+    ExtensionRange,
+    Field,
+    OneOf,
+    Enum,
+    EnumValue,
+    Service,
+    Method,
+}
+
+impl Target {
+    const ALL: [Self; 9] = [
+        Self::File,
+        Self::Message,
+        Self::ExtensionRange,
+        Self::Field,
+        Self::OneOf,
+        Self::Enum,
+        Self::EnumValue,
+        Self::Service,
+        Self::Method,
+    ];
+
+    fn message_name(self) -> &'static str {
+        match self {
+            Self::File => "FileOptions",
+            Self::Message => "MessageOptions",
+            Self::ExtensionRange => "ExtensionRangeOptions",
+            Self::Field => "FieldOptions",
+            Self::OneOf => "OneofOptions",
+            Self::Enum => "EnumOptions",
+            Self::EnumValue => "EnumValueOptions",
+            Self::Service => "ServiceOptions",
+            Self::Method => "MethodOptions",
+        }
+    }
+
+    fn describe(self) -> &'static str {
+        match self {
+            Self::File => "file",
+            Self::Message => "message",
+            Self::ExtensionRange => "extension range",
+            Self::Field => "field",
+            Self::OneOf => "oneof",
+            Self::Enum => "enum",
+            Self::EnumValue => "enum value",
+            Self::Service => "service",
+            Self::Method => "rpc",
+        }
+    }
+
+    fn options_message(self) -> &'static ProtoMessage {
+        well_known::descriptor()
+            .messages()
+            .iter()
+            .find(|message| message.name() == self.message_name())
+            .expect("descriptor.proto declares every options message")
+    }
+
+    // from_full_name is the target whose options message is `full_name`
+    fn from_full_name(full_name: &str) -> Option<Self> {
+        let name = full_name.strip_prefix("google.protobuf.")?;
+        Self::ALL
+            .into_iter()
+            .find(|target| target.message_name() == name)
+    }
+}
+
+struct OptionCheck<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    // Whether the options being checked are set within a deprecated declaration
+    within_deprecated: bool,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl<'a> OptionCheck<'a> {
+    fn options(&mut self, scope: &str, target: Target, options: &[ProtoOption]) {
+        for option in options {
+            if option.name().starts_with('(') {
+                self.custom(scope, target, option);
+                continue;
+            }
+            // Repeats of these are reported by the field checks
+            if target == Target::Field && matches!(option.name(), "default" | "json_name") {
+                continue;
+            }
+            self.built_in(target, option);
+        }
+    }
+
+    // built_in resolves a built in option through the fields of the options message, then each
+    // later part of its name as a field of the previous part's message type, before checking the
+    // value
+    fn built_in(&mut self, target: Target, option: &ProtoOption) {
+        let parts = split_name(option.name());
+        let mut message = target.options_message();
+        let mut full_name = format!("google.protobuf.{}", message.name());
+        let Some(mut field) = message
+            .fields()
+            .iter()
+            .find(|field| field.name() == parts[0])
+        else {
+            self.unknown(target, option, parts[0]);
+            return;
+        };
+        for (i, part) in parts.iter().enumerate().skip(1) {
+            // Extensions like `features.(pb.cpp)` aren't declared in descriptor.proto
+            if part.starts_with(['(', '[']) {
+                return;
+            }
+            let Some((name, field_message)) = descriptor_message(&full_name, message, field) else {
+                let path = parts[..i].join(".");
+                self.diagnostics.push(Diagnostic::error(
+                    option.span(),
+                    format!("{path} isn't a message, so it has no field {part}"),
+                ));
+                return;
+            };
+            (full_name, message) = (name, field_message);
+            let Some(member) = message.fields().iter().find(|field| field.name() == *part) else {
+                self.diagnostics.push(Diagnostic::error(
+                    option.span(),
+                    format!("{full_name} has no field {part}"),
+                ));
+                return;
+            };
+            field = member;
+        }
+        if let Err(expected) = check_value(message, field, option.value()) {
+            self.diagnostics.push(Diagnostic::error(
+                option.span(),
+                format!("option {} must be {expected}", option.name()),
+            ));
+        }
+    }
+
+    fn unknown(&mut self, target: Target, option: &ProtoOption, name: &str) {
+        let elsewhere: Vec<_> = Target::ALL
+            .into_iter()
+            .filter(|other| {
+                other
+                    .options_message()
+                    .fields()
+                    .iter()
+                    .any(|field| field.name() == name)
+            })
+            .collect();
+        let message = if elsewhere.is_empty() {
+            format!("unknown {} option {name}", target.describe())
+        } else {
+            wrong_target(name, &elsewhere, target)
+        };
+        self.diagnostics
+            .push(Diagnostic::error(option.span(), message));
+    }
+
+    // custom resolves the extension a custom option names, then each later part of its name as a
+    // field of the previous part's message type, before checking the value
+    fn custom(&mut self, scope: &str, target: Target, option: &ProtoOption) {
+        let parts = split_name(option.name());
+        let extension = match self.symbols.resolve_extension(scope, unwrap(parts[0])) {
+            Ok(extension) => extension,
+            // The extension may be declared in an import
+            Err(_) if !self.symbols.imports_loaded() => return,
+            Err(unresolved) => {
+                self.diagnostics
+                    .push(Diagnostic::error(option.span(), unresolved.to_string()));
+                return;
+            }
+        };
+        self.deprecated_use(option.span(), extension);
+        // Unresolved extendees are reported in the file declaring the extension
+        let Some(extendee) = self.extendee(extension) else {
+            return;
+        };
+        if extendee.full_name() != format!("google.protobuf.{}", target.message_name()) {
+            let message = match Target::from_full_name(extendee.full_name()) {
+                Some(other) => wrong_target(parts[0], &[other], target),
+                None => format!(
+                    "{} extends {}, it isn't an option",
+                    parts[0],
+                    extendee.full_name()
+                ),
+            };
+            self.diagnostics
+                .push(Diagnostic::error(option.span(), message));
+            return;
+        }
+
+        let mut field = extension;
+        for (i, part) in parts.iter().enumerate().skip(1) {
+            let path = parts[..i].join(".");
+            match self.member(scope, &path, field, part) {
+                Ok(member) => {
+                    self.deprecated_use(option.span(), member);
+                    field = member;
+                }
+                Err(message) => {
+                    self.diagnostics
+                        .push(Diagnostic::error(option.span(), message));
+                    return;
+                }
+            }
+        }
+        self.value(scope, option.span(), option.name(), field, option.value());
+    }
+
+    // member resolves `name` within the message type of `field`, either a field or an extension
+    // within parentheses or brackets
+    fn member(
+        &self,
+        scope: &str,
+        path: &str,
+        field: &Symbol,
+        name: &str,
+    ) -> Result<&'a Symbol, String> {
+        let Some(message) = self
+            .field_type(field)
+            .filter(|symbol| symbol.kind() == SymbolKind::Message)
+        else {
+            return Err(format!("{path} isn't a message, so it has no field {name}"));
+        };
+
+        if name.starts_with(['(', '[']) {
+            let extension = self
+                .symbols
+                .resolve_extension(scope, unwrap(name))
+                .map_err(|unresolved| unresolved.to_string())?;
+            let extends = self
+                .extendee(extension)
+                .is_some_and(|extendee| extendee.full_name() == message.full_name());
+            if !extends {
+                return Err(format!(
+                    "{} doesn't extend {}",
+                    extension.full_name(),
+                    message.full_name()
+                ));
+            }
+            return Ok(extension);
+        }
+        self.symbols
+            .table()
+            .lookup(&format!("{}.{name}", message.full_name()))
+            .filter(|symbol| symbol.kind() == SymbolKind::Field)
+            .ok_or_else(|| format!("{} has no field {name}", message.full_name()))
+    }
+
+    // extendee resolves the message an extension extends. Like field types, it's resolved from the
+    // whole table as the name is used by the file declaring the extension rather than this one.
+    fn extendee(&self, extension: &Symbol) -> Option<&'a Symbol> {
+        let extendee = extension.extendee()?;
+        self.symbols
+            .table()
+            .resolve_type(extension.scope(), &extendee.name())
+            .ok()
+    }
+
+    // field_type resolves the message or enum type of a field, `None` for scalars
+    fn field_type(&self, field: &Symbol) -> Option<&'a Symbol> {
+        let field_type = field.field_type()?;
+        if !matches!(
+            field_type,
+            ProtoFieldType::Identifier(_) | ProtoFieldType::FullIdentifier(_)
+        ) {
+            return None;
+        }
+        self.symbols
+            .table()
+            .resolve_type(field.scope(), &field_type.name())
+            .ok()
+    }
+
+    // value checks `value` fits the type of `field`, aggregates are checked field by field
+    fn value(
+        &mut self,
+        scope: &str,
+        span: Span,
+        path: &str,
+        field: &Symbol,
+        value: &ProtoConstant,
+    ) {
+        if let ProtoConstant::List(values) = value {
+            if !field.repeated() {
+                self.diagnostics.push(Diagnostic::error(
+                    span,
+                    format!("option {path} must be a single value, it isn't repeated"),
+                ));
+                return;
+            }
+            for value in values {
+                self.value(scope, span, path, field, value);
+            }
+            return;
+        }
+        let Some(field_type) = field.field_type() else {
+            return;
+        };
+        let expected = match scalar(field_type, value) {
+            Some(result) => result,
+            // Unresolved types are reported in the file declaring the field
+            None => match self.field_type(field) {
+                Some(symbol) if symbol.kind() == SymbolKind::Enum => {
+                    self.enum_value(span, symbol, value)
+                }
+                Some(_) => {
+                    let ProtoConstant::Aggregate(fields) = value else {
+                        self.diagnostics.push(Diagnostic::error(
+                            span,
+                            format!("option {path} must be a message within braces"),
+                        ));
+                        return;
+                    };
+                    for aggregate in fields {
+                        // `Any` values are written with their type URL, `[type.googleapis.com/..]`
+                        if aggregate.name().contains('/') {
+                            continue;
+                        }
+                        match self.member(scope, path, field, aggregate.name()) {
+                            Ok(member) => {
+                                self.deprecated_use(span, member);
+                                let path = format!("{path}.{}", aggregate.name());
+                                self.value(scope, span, &path, member, aggregate.value());
+                            }
+                            Err(message) => self.diagnostics.push(Diagnostic::error(span, message)),
+                        }
+                    }
+                    Ok(())
+                }
+                None => Ok(()),
+            },
+        };
+        if let Err(expected) = expected {
+            self.diagnostics.push(Diagnostic::error(
+                span,
+                format!("option {path} must be {expected}"),
+            ));
+        }
+    }
+
+    fn enum_value(
+        &mut self,
+        span: Span,
+        enumeration: &Symbol,
+        value: &ProtoConstant,
+    ) -> Result<(), String> {
+        let mut values: Vec<_> = self
+            .symbols
+            .table()
+            .children(enumeration.scope())
+            .filter(|symbol| {
+                symbol.kind() == SymbolKind::EnumValue && symbol.parent() == enumeration.full_name()
+            })
+            .collect();
+        values.sort_by_key(|value| value.span().start().offset());
+        let used = values.iter().find(
+            |symbol| matches!(value, ProtoConstant::Identifier(name) if symbol.name() == name),
+        );
+        if let Some(used) = used {
+            self.deprecated_use(span, used);
+            return Ok(());
+        }
+        let values: Vec<_> = values.into_iter().map(Symbol::name).collect();
+        Err(format!("one of {}", join(&values)))
+    }
+
+    // deprecated_use warns about an option using `symbol` unless it's set within a deprecated
+    // declaration
+    fn deprecated_use(&mut self, span: Span, symbol: &Symbol) {
+        if !self.within_deprecated {
+            self.diagnostics.extend(deprecated_use(span, symbol));
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for OptionCheck<'_> {
+    fn visit_file(&mut self, ctx: &mut Context, file: &'ast ProtoFile) {
+        self.within_deprecated = false;
+        self.options(&ctx.scope(), Target::File, file.options());
+        visit::walk_file(self, ctx, file);
+    }
+
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        // A message's options are set within it so they resolve from its scope
+        let scope = ctx.full_name(message.name());
+        self.within_deprecated = within_deprecated(self.symbols, &scope);
+        self.options(&scope, Target::Message, message.options());
+        // Ranges declared together share their options, so each statement's are checked once
+        let ranges = message
+            .extension_ranges()
+            .chunk_by(|a, b| a.options() == b.options());
+        for group in ranges {
+            self.options(&scope, Target::ExtensionRange, group[0].options());
+        }
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        self.within_deprecated = within_deprecated(self.symbols, &ctx.full_name(field.name()));
+        self.options(&ctx.scope(), Target::Field, field.options());
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_oneof(&mut self, ctx: &mut Context, oneof: &'ast ProtoOneOf) {
+        self.within_deprecated = within_deprecated(self.symbols, &ctx.full_name(oneof.name()));
+        self.options(&ctx.scope(), Target::OneOf, oneof.options());
+        visit::walk_oneof(self, ctx, oneof);
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, enumeration: &'ast ProtoEnum) {
+        let name = ctx.full_name(enumeration.name());
+        self.within_deprecated = within_deprecated(self.symbols, &name);
+        self.options(&ctx.scope(), Target::Enum, enumeration.options());
+        visit::walk_enum(self, ctx, enumeration);
+    }
+
+    fn visit_enum_value(&mut self, ctx: &mut Context, value: &'ast ProtoEnumValue) {
+        // Enum values are scoped alongside their enum, so it isn't one of the enclosing names
+        let enumeration = ctx.full_name(ctx.enumeration().unwrap_or_default());
+        self.within_deprecated = within_deprecated(self.symbols, &enumeration)
+            || within_deprecated(self.symbols, &ctx.full_name(value.name()));
+        self.options(&ctx.scope(), Target::EnumValue, value.options());
+        visit::walk_enum_value(self, ctx, value);
+    }
+
+    fn visit_service(&mut self, ctx: &mut Context, service: &'ast ProtoService) {
+        let name = ctx.full_name(service.name());
+        self.within_deprecated = within_deprecated(self.symbols, &name);
+        self.options(&ctx.scope(), Target::Service, service.options());
+        visit::walk_service(self, ctx, service);
+    }
+
+    fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+        let name = format!("{}.{}", ctx.service().unwrap_or_default(), rpc.name());
+        self.within_deprecated = within_deprecated(self.symbols, &ctx.full_name(&name));
+        self.options(&ctx.scope(), Target::Method, rpc.options());
+        visit::walk_rpc(self, ctx, rpc);
+    }
+}
+
+// wrong_target describes setting the option `name` declared for `targets` on another target
+fn wrong_target(name: &str, targets: &[Target], used_on: Target) -> String {
+    let targets: Vec<_> = targets.iter().map(|target| target.describe()).collect();
+    format!(
+        "{name} is a{} {} option, it can't be set on a{} {}",
+        article(targets[0]),
+        join(&targets),
+        article(used_on.describe()),
+        used_on.describe()
+    )
+}
+
+// split_name splits an option name into its parts, keeping the parentheses around extensions,
+// `(foo.bar).baz` is `(foo.bar)` & `baz`
+fn split_name(name: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut start = 0;
+    let mut depth = 0;
+    for (i, c) in name.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            '.' if depth == 0 => {
+                parts.push(&name[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&name[start..]);
+    parts
+}
+
+// unwrap removes the parentheses or brackets around an extension name
+fn unwrap(name: &str) -> &str {
+    name.trim_start_matches(['(', '['])
+        .trim_end_matches([')', ']'])
+}
+
+// scalar describes the values a scalar field accepts when `value` isn't one, it's `None` for
+// message & enum types
+fn scalar(field_type: &ProtoFieldType, value: &ProtoConstant) -> Option<Result<(), String>> {
+    use ProtoFieldType as T;
+
+    let int = |min: i128, max: i128| match value {
+        ProtoConstant::Int(i) if (min..=max).contains(i) => Ok(()),
+        _ => Err(format!("an integer between {min} & {max}")),
+    };
+    Some(match field_type {
+        T::Int32 | T::Sint32 | T::Sfixed32 => int(i32::MIN.into(), i32::MAX.into()),
+        T::Int64 | T::Sint64 | T::Sfixed64 => int(i64::MIN.into(), i64::MAX.into()),
+        T::Uint32 | T::Fixed32 => int(0, u32::MAX.into()),
+        T::Uint64 | T::Fixed64 => int(0, u64::MAX.into()),
+        T::Float | T::Double => match value {
+            ProtoConstant::Float(_) | ProtoConstant::Int(_) => Ok(()),
+            _ => Err("a number".to_owned()),
+        },
+        T::Bool => match value {
+            ProtoConstant::Bool(_) => Ok(()),
+            _ => Err("true or false".to_owned()),
+        },
+        T::String => match value {
+            ProtoConstant::String(_) => Ok(()),
+            _ => Err("a string".to_owned()),
+        },
+        T::Bytes => match value {
+            ProtoConstant::String(_) | ProtoConstant::Bytes(_) => Ok(()),
+            _ => Err("a string".to_owned()),
+        },
+        T::Identifier(_) | T::FullIdentifier(_) | T::Map { .. } => return None,
+    })
+}
+
+// descriptor_message finds the message type of `field` in `message`, named `full_name`, among
+// the messages of descriptor.proto, `None` for scalars & enums
+fn descriptor_message(
+    full_name: &str,
+    message: &'static ProtoMessage,
+    field: &MessageField,
+) -> Option<(String, &'static ProtoMessage)> {
+    let type_name = field.field_type().name();
+    let type_name = type_name.rsplit('.').next().unwrap_or_default();
+    // The types of fields are declared either within their message or at the top level
+    if let Some(nested) = message.messages().iter().find(|m| m.name() == type_name) {
+        return Some((format!("{full_name}.{type_name}"), nested));
+    }
+    well_known::descriptor()
+        .messages()
+        .iter()
+        .find(|m| m.name() == type_name)
+        .map(|m| (format!("google.protobuf.{type_name}"), m))
+}
+
+// check_value describes the values a field of the options message accepts when `value` isn't one
+fn check_value(
+    message: &ProtoMessage,
+    field: &MessageField,
+    value: &ProtoConstant,
+) -> Result<(), String> {
+    if let ProtoConstant::List(values) = value {
+        if field.label() != Some(ProtoFieldLabel::Repeated) {
+            return Err("a single value, it isn't repeated".to_owned());
+        }
+        return values
+            .iter()
+            .try_for_each(|value| check_value(message, field, value));
+    }
+    if let Some(result) = scalar(field.field_type(), value) {
+        return result;
+    }
+    let type_name = field.field_type().name();
+    let type_name = type_name.rsplit('.').next().unwrap_or_default();
+    // The enums of options are declared within the options message
+    let enumeration = message
+        .enums()
+        .iter()
+        .chain(well_known::descriptor().enums())
+        .find(|enumeration| enumeration.name() == type_name);
+    match enumeration {
+        Some(enumeration) => match value {
+            ProtoConstant::Identifier(name)
+                if enumeration.values().iter().any(|v| v.name() == name) =>
+            {
+                Ok(())
+            }
+            _ => {
+                let names: Vec<_> = enumeration.values().iter().map(|v| v.name()).collect();
+                Err(format!("one of {}", join(&names)))
+            }
+        },
+        None => match value {
+            ProtoConstant::Aggregate(_) => Ok(()),
+            _ => Err("a message within braces".to_owned()),
+        },
+    }
+}
+
+// article completes "a" for `noun`, "oneof" starts with a consonant sound
+fn article(noun: &str) -> &'static str {
+    if noun.starts_with(['a', 'e', 'i', 'o', 'u']) && !noun.starts_with("one") {
+        "n"
+    } else {
+        ""
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::validate::errors;
+    use crate::workspace::Workspace;
+
+    #[test]
+    fn built_in_options() {
+        assert_eq!(
+            Vec::<String>::new(),
+            errors(
+                r#"syntax = "proto2";
+option java_multiple_files = true;
+option optimize_for = CODE_SIZE;
+option java_package = "com.foo";
+message Foo {
+    option deprecated = true;
+    repeated int32 a = 1 [packed = true, ctype = CORD];
+    oneof choice { int32 b = 2 [lazy = false]; }
+}
+enum Kind {
+    option allow_alias = true;
+    KIND_UNKNOWN = 0 [deprecated = true];
+    KIND_DEFAULT = 0;
+}
+service Svc {
+    option deprecated = true;
+    rpc Get(Foo) returns (Foo) { option idempotency_level = NO_SIDE_EFFECTS; }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn newer_built_in_options() {
+        assert_eq!(
+            vec![
+                "6:28: error: option verification must be one of DECLARATION & UNVERIFIED",
+                "7:28: error: debug_redact is a field & enum value option, it can't be set on an \
+                 extension range",
+            ],
+            errors(
+                r#"syntax = "proto2";
+message Foo {
+    optional string token = 1 [debug_redact = true, retention = RETENTION_SOURCE];
+    optional int32 a = 2 [targets = TARGET_TYPE_FIELD, targets = TARGET_TYPE_FILE];
+    extensions 100 to 199, 300 [verification = UNVERIFIED];
+    extensions 400 to max [verification = CHECKED];
+    extensions 200 to 299 [debug_redact = true];
+}
+enum Kind {
+    KIND_UNKNOWN = 0;
+    KIND_SECRET = 1 [debug_redact = true];
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn invalid_options() {
+        assert_eq!(
+            vec![
+                "2:1: error: option java_multiple_files must be true or false",
+                "3:1: error: option optimize_for must be one of SPEED, CODE_SIZE & LITE_RUNTIME",
+                "4:1: error: option java_package must be a string",
+                "5:1: error: unknown file option missing",
+                "7:5: error: packed is a field option, it can't be set on a message",
+                "8:27: error: allow_alias is an enum option, it can't be set on a field",
+                "8:47: error: option jstype must be one of JS_NORMAL, JS_STRING & JS_NUMBER",
+                "9:15: error: deprecated is a file, message, field, enum, enum value, service & \
+                 rpc option, it can't be set on a oneof",
+            ],
+            errors(
+                r#"syntax = "proto2";
+option java_multiple_files = "yes";
+option optimize_for = FAST;
+option java_package = 1;
+option missing = true;
+message Foo {
+    option packed = true;
+    optional int64 a = 1 [allow_alias = true, jstype = JS_BIGINT];
+    oneof o { option deprecated = true; int32 b = 2; }
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn dotted_built_in_options() {
+        assert_eq!(
+            vec![
+                "5:18: error: option features.field_presence must be one of \
+                 FIELD_PRESENCE_UNKNOWN, EXPLICIT, IMPLICIT & LEGACY_REQUIRED",
+                "6:18: error: google.protobuf.FeatureSet has no field presence",
+                "7:18: error: deprecated isn't a message, so it has no field x",
+            ],
+            errors(
+                r#"syntax = "proto3";
+option features.field_presence = IMPLICIT;
+message Foo {
+    int32 a = 1 [features.enum_type = OPEN];
+    int32 b = 2 [features.field_presence = OPEN];
+    int32 c = 3 [features.presence = EXPLICIT];
+    int32 d = 4 [deprecated.x = true];
+}"#
+            )
+        );
+    }
+
+    #[test]
+    fn custom_options() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        workspace.open(
+            "rules.proto",
+            r#"syntax = "proto3";
+package validate;
+import "google/protobuf/descriptor.proto";
+extend google.protobuf.FieldOptions { FieldRules rules = 1071; }
+extend google.protobuf.MessageOptions { bool disabled = 1071; }
+message FieldRules {
+    StringRules string = 14;
+    int32 count = 2;
+}
+message StringRules {
+    bool uuid = 22;
+    Kind kind = 1;
+    repeated string in = 2;
+}
+enum Kind {
+    KIND_UNKNOWN = 0;
+    KIND_A = 1;
+}"#,
+        );
+        workspace.open(
+            "test.proto",
+            r#"syntax = "proto3";
+package validate.test;
+import "rules.proto";
+message Foo {
+    option (validate.disabled) = true;
+    string a = 1 [(validate.rules).string.uuid = true];
+    string b = 2 [(.validate.rules) = { string: { kind: KIND_A in: ["x", "y"] } count: 1 }];
+    string c = 3 [(validate.rule).string.uuid = true];
+    string d = 4 [(rules).strin.uuid = true];
+    string e = 5 [(rules).string.uuid = "yes"];
+    string f = 6 [(rules) = { string { kind: KIND_B in: [1] } cnt: 1 }];
+    string g = 7 [(disabled) = true];
+    string h = 8 [(rules).count.x = 1];
+    string i = 9 [(rules) = { string { uuid: [true] in: ["x", "y"] } count: [1, 2] }];
+}"#,
+        );
+
+        assert_eq!(
+            vec![
+                "8:19: error: unresolved extension validate.rule, tried \
+                 validate.test.Foo.validate.rule, validate.test.validate.rule, \
+                 validate.validate.rule & validate.rule",
+                "9:19: error: validate.FieldRules has no field strin",
+                "10:19: error: option (rules).string.uuid must be true or false",
+                "11:19: error: option (rules).string.kind must be one of KIND_UNKNOWN & KIND_A",
+                "11:19: error: option (rules).string.in must be a string",
+                "11:19: error: validate.FieldRules has no field cnt",
+                "12:19: error: (disabled) is a message option, it can't be set on a field",
+                "13:19: error: (rules).count isn't a message, so it has no field x",
+                "14:19: error: option (rules).string.uuid must be a single value, it isn't \
+                 repeated",
+                "14:19: error: option (rules).count must be a single value, it isn't repeated",
+            ],
+            workspace
+                .diagnostics("test.proto")
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+    }
+}
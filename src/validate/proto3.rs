@@ -0,0 +1,216 @@
+//! Checks for the proto2 features proto3 files can't use, each with its own [`Code`]
+
+use crate::ast::{
+    MessageField, ProtoEnum, ProtoExtend, ProtoFieldLabel, ProtoFieldType, ProtoFile, ProtoMessage,
+    ProtoSyntax,
+};
+use crate::diagnostics::{Code, Diagnostic};
+use crate::span::Span;
+use crate::symbols::{SymbolKind, VisibleSymbols};
+use crate::visit::{self, Context, Visitor};
+
+use super::resolved;
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    if *file.syntax() != ProtoSyntax::Proto3 {
+        return;
+    }
+    let mut check = Proto3Check {
+        symbols,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct Proto3Check<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl Proto3Check<'_> {
+    fn error(&mut self, code: Code, span: Span, message: String) {
+        self.diagnostics
+            .push(Diagnostic::error(span, message).with_code(code));
+    }
+
+    // closed_enum reports fields using an enum declared in a proto2 file, proto3 expects every enum
+    // to accept unknown values
+    fn closed_enum(&mut self, ctx: &Context, field: &MessageField) {
+        let field_type = match field.field_type() {
+            ProtoFieldType::Map { value, .. } => value,
+            field_type => field_type,
+        };
+        if !matches!(
+            field_type,
+            ProtoFieldType::Identifier(_) | ProtoFieldType::FullIdentifier(_)
+        ) {
+            return;
+        }
+        let Some(symbol) = resolved(self.symbols, ctx, field_type) else {
+            return;
+        };
+        if symbol.kind() == SymbolKind::Enum && symbol.syntax() == ProtoSyntax::Proto2 {
+            let message = format!(
+                "{} is a closed proto2 enum, proto3 fields can only use open enums",
+                symbol.full_name()
+            );
+            self.error(Code::Proto3ClosedEnum, field.span(), message);
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for Proto3Check<'_> {
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        for range in message.extension_ranges() {
+            self.error(
+                Code::Proto3Extensions,
+                range.span(),
+                "extension ranges aren't allowed in proto3".to_owned(),
+            );
+        }
+        visit::walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        if field.label() == Some(ProtoFieldLabel::Required) {
+            self.error(
+                Code::Proto3Required,
+                field.span(),
+                format!("{} can't be required in proto3", field.name()),
+            );
+        }
+        if let Some(group) = field.group() {
+            self.error(
+                Code::Proto3Group,
+                field.span(),
+                format!(
+                    "groups aren't allowed in proto3, declare {} as a nested message instead",
+                    group.name()
+                ),
+            );
+        }
+        if let Some(default) = field.default() {
+            self.error(
+                Code::Proto3Default,
+                default.span(),
+                "explicit default values aren't allowed in proto3".to_owned(),
+            );
+        }
+        self.closed_enum(ctx, field);
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, enumeration: &'ast ProtoEnum) {
+        if let Some(first) = enumeration.values().first() {
+            if first.number() != 0 {
+                let message = format!(
+                    "the first value of a proto3 enum must be zero, {} is {}",
+                    first.name(),
+                    first.number()
+                );
+                self.error(Code::Proto3FirstEnumValue, first.span(), message);
+            }
+        }
+        visit::walk_enum(self, ctx, enumeration);
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        if let Some(symbol) = resolved(self.symbols, ctx, extend.extendee()) {
+            if !is_options(symbol.full_name()) {
+                let message = format!(
+                    "proto3 files can only extend the google.protobuf options messages, not {}",
+                    symbol.full_name()
+                );
+                self.error(Code::Proto3Extend, extend.span(), message);
+            }
+        }
+        visit::walk_extend(self, ctx, extend);
+    }
+}
+
+// is_options is whether the message is one of descriptor.proto's options, which custom options
+// extend, `google.protobuf.FieldOptions`
+fn is_options(full_name: &str) -> bool {
+    full_name
+        .strip_prefix("google.protobuf.")
+        .is_some_and(|name| !name.contains('.') && name.ends_with("Options"))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::diagnostics::Code;
+    use crate::validate::errors;
+    use crate::workspace::Workspace;
+
+    #[test]
+    fn proto2_features() {
+        assert_eq!(
+            vec![
+                "4:16: error[proto3-extensions]: extension ranges aren't allowed in proto3",
+                "5:5: error[proto3-required]: a can't be required in proto3",
+                "6:18: error[proto3-default]: explicit default values aren't allowed in proto3",
+                "7:5: error[proto3-group]: groups aren't allowed in proto3, declare Result as a \
+                 nested message instead",
+                "10:13: error[proto3-first-enum-value]: the first value of a proto3 enum must be \
+                 zero, KIND_A is 1",
+                "12:1: error[proto3-extend]: proto3 files can only extend the google.protobuf \
+                 options messages, not Foo",
+            ],
+            errors(
+                r#"syntax = "proto3";
+import "google/protobuf/descriptor.proto";
+message Foo {
+    extensions 100 to 200;
+    required int32 a = 1;
+    int32 b = 2 [default = 1];
+    repeated group Result = 3 { string url = 4; }
+    Kind kind = 5;
+}
+enum Kind { KIND_A = 1; KIND_B = 0; }
+extend google.protobuf.FieldOptions { string rule = 50000; }
+extend Foo { int32 c = 100; }"#
+            )
+        );
+    }
+
+    #[test]
+    fn closed_enums() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        workspace.open(
+            "closed.proto",
+            r#"syntax = "proto2"; package closed; enum Kind { KIND_UNKNOWN = 0; }"#,
+        );
+        workspace.open(
+            "open.proto",
+            r#"syntax = "proto3";
+import "closed.proto";
+message Foo {
+    closed.Kind a = 1;
+    map<string, closed.Kind> b = 2;
+    Open c = 3;
+}
+enum Open { OPEN_UNKNOWN = 0; }"#,
+        );
+
+        let diagnostics = workspace.diagnostics("open.proto");
+        assert_eq!(
+            vec![
+                "4:5: error[proto3-closed-enum]: closed.Kind is a closed proto2 enum, proto3 \
+                 fields can only use open enums",
+                "5:5: error[proto3-closed-enum]: closed.Kind is a closed proto2 enum, proto3 \
+                 fields can only use open enums",
+            ],
+            diagnostics
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(Some(Code::Proto3ClosedEnum), diagnostics[0].code());
+    }
+}
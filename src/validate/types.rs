@@ -0,0 +1,146 @@
+//! Checks that every type used by a field, rpc or extend resolves to a declared message or enum
+
+use crate::ast::{MessageField, ProtoExtend, ProtoFieldType, ProtoFile, ProtoRpc};
+use crate::diagnostics::Diagnostic;
+use crate::span::Span;
+use crate::symbols::{Symbol, SymbolKind, VisibleSymbols};
+use crate::visit::{self, Context, Visitor};
+
+pub(super) fn check(
+    file: &ProtoFile,
+    symbols: &VisibleSymbols<'_>,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut check = TypeCheck {
+        symbols,
+        diagnostics,
+    };
+    visit::visit(&mut check, file);
+}
+
+struct TypeCheck<'a> {
+    symbols: &'a VisibleSymbols<'a>,
+    diagnostics: &'a mut Vec<Diagnostic>,
+}
+
+impl TypeCheck<'_> {
+    // resolve reports the type when it doesn't resolve, scalars resolve to `None`
+    fn resolve(
+        &mut self,
+        ctx: &Context,
+        field_type: &ProtoFieldType,
+        span: Span,
+    ) -> Option<&Symbol> {
+        let name = match field_type {
+            ProtoFieldType::Identifier(_) | ProtoFieldType::FullIdentifier(_) => field_type.name(),
+            ProtoFieldType::Map { value, .. } => return self.resolve(ctx, value, span),
+            _ => return None,
+        };
+        match self.symbols.resolve_type(&ctx.scope(), &name) {
+            Ok(symbol) => Some(symbol),
+            // The type may be declared in an import
+            Err(_) if !self.symbols.imports_loaded() => None,
+            Err(unresolved) => {
+                self.diagnostics
+                    .push(Diagnostic::error(span, unresolved.to_string()));
+                None
+            }
+        }
+    }
+
+    fn expect_message(&mut self, ctx: &Context, field_type: &ProtoFieldType, span: Span) {
+        if let Some(symbol) = self.resolve(ctx, field_type, span) {
+            if symbol.kind() != SymbolKind::Message {
+                let message = format!("{} is an enum, not a message", symbol.full_name());
+                self.diagnostics.push(Diagnostic::error(span, message));
+            }
+        }
+    }
+}
+
+impl<'ast> Visitor<'ast> for TypeCheck<'_> {
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        self.resolve(ctx, field.field_type(), field.span());
+        visit::walk_field(self, ctx, field);
+    }
+
+    fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+        self.expect_message(ctx, rpc.request().message_type(), rpc.span());
+        self.expect_message(ctx, rpc.returns().message_type(), rpc.span());
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        self.expect_message(ctx, extend.extendee(), extend.span());
+        visit::walk_extend(self, ctx, extend);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::validate::errors;
+
+    #[test]
+    fn resolved_types() {
+        assert_eq!(
+            Vec::<String>::new(),
+            errors(
+                r#"syntax = "proto3";
+package foo.bar;
+message Outer {
+    Inner a = 1;
+    .foo.bar.Outer.Inner b = 2;
+    map<string, bar.Kind> c = 3;
+    message Inner { Outer.Inner d = 1; }
+}
+enum Kind { KIND_UNKNOWN = 0; }
+service Svc { rpc Get(Outer) returns (Outer.Inner); }"#
+            )
+        );
+    }
+
+    #[test]
+    fn unloaded_imports() {
+        // Without a workspace the imports aren't loaded, so their names can't be checked
+        assert_eq!(
+            Vec::<String>::new(),
+            errors(
+                r#"syntax = "proto3";
+import "other.proto";
+message Foo {
+    other.Bar a = 1 [(other.rules).min = 1];
+    map<string, Baz> b = 2;
+}
+service Svc { rpc Get(Foo) returns (other.Bar); }
+extend other.Options { string c = 1000; }"#
+            )
+        );
+    }
+
+    #[test]
+    fn unresolved_types() {
+        assert_eq!(
+            vec![
+                "4:5: error: unresolved type Missing, tried foo.Outer.Missing, foo.Missing & Missing",
+                "5:5: error: unresolved type .Outer, tried Outer",
+                "6:5: error: unresolved type Kind.Missing, tried foo.Outer.Kind.Missing",
+                "10:15: error: unresolved type Missing, tried foo.Missing & Missing",
+                "10:15: error: foo.Outer.Kind is an enum, not a message",
+                "12:1: error: foo.Outer.Kind is an enum, not a message",
+            ],
+            errors(
+                r#"syntax = "proto2";
+package foo;
+message Outer {
+    optional Missing a = 1;
+    map<string, .Outer> b = 2;
+    optional Kind.Missing c = 3;
+    enum Kind { KIND_UNKNOWN = 0; }
+    extensions 100 to 200;
+}
+service Svc { rpc Get(Missing) returns (Outer.Kind); }
+extend Outer { optional Outer.Kind d = 100; }
+extend Outer.Kind { optional int32 e = 100; }"#
+            )
+        );
+    }
+}
@@ -0,0 +1,677 @@
+//! Traversal of a [`ProtoFile`], in the style of `syn::visit`.
+//!
+//! Each `visit_*` method on [`Visitor`] & [`VisitorMut`] defaults to calling the matching `walk_*`
+//! function, which visits the node's children. Override the methods for the nodes a pass cares
+//! about & call the `walk_*` function from within them to keep descending.
+//!
+//! ```
+//! use proto_ls::{ast::MessageField, visit::{self, Context, Visitor}};
+//!
+//! #[derive(Default)]
+//! struct FieldNames(Vec<String>);
+//!
+//! impl<'ast> Visitor<'ast> for FieldNames {
+//!     fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+//!         self.0.push(ctx.full_name(field.name()));
+//!     }
+//! }
+//!
+//! let file = proto_ls::parse("syntax = \"proto3\"; package foo; message Bar { string baz = 1; }")
+//!     .unwrap();
+//! let mut names = FieldNames::default();
+//! visit::visit(&mut names, &file);
+//! assert_eq!(vec!["foo.Bar.baz"], names.0);
+//! ```
+
+use crate::ast::{
+    MessageField, ProtoEnum, ProtoEnumValue, ProtoExtend, ProtoFieldType, ProtoFile, ProtoImport,
+    ProtoMessage, ProtoOneOf, ProtoOption, ProtoRpc, ProtoRpcType, ProtoService,
+};
+
+/// Where the node currently being visited is declared, maintained by the `walk_*` functions
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Context {
+    package: Vec<String>,
+    messages: Vec<String>,
+    oneof: Option<String>,
+    enumeration: Option<String>,
+    service: Option<String>,
+    extend: bool,
+}
+
+impl Context {
+    pub fn package(&self) -> &[String] {
+        &self.package
+    }
+
+    /// The names of the enclosing messages, outermost first
+    pub fn messages(&self) -> &[String] {
+        &self.messages
+    }
+
+    pub fn oneof(&self) -> Option<&str> {
+        self.oneof.as_deref()
+    }
+
+    pub fn enumeration(&self) -> Option<&str> {
+        self.enumeration.as_deref()
+    }
+
+    pub fn service(&self) -> Option<&str> {
+        self.service.as_deref()
+    }
+
+    /// Whether the node is within an `extend` block
+    pub fn in_extend(&self) -> bool {
+        self.extend
+    }
+
+    /// The fully qualified scope names are declared in, `foo.bar.Outer.Inner`. Enum values are
+    /// scoped as siblings of their enum so the enum name isn't included, oneofs don't introduce a
+    /// scope either.
+    pub fn scope(&self) -> String {
+        let mut parts = self.package.iter().chain(&self.messages);
+        let Some(first) = parts.next() else {
+            return String::new();
+        };
+        parts.fold(first.clone(), |scope, part| scope + "." + part)
+    }
+
+    /// The fully qualified name of `name` declared within the current scope
+    pub fn full_name(&self, name: &str) -> String {
+        let scope = self.scope();
+        if scope.is_empty() {
+            name.to_owned()
+        } else {
+            scope + "." + name
+        }
+    }
+}
+
+/// Visits every node of a file by reference
+pub fn visit<'ast, V: Visitor<'ast> + ?Sized>(v: &mut V, file: &'ast ProtoFile) {
+    v.visit_file(&mut Context::default(), file);
+}
+
+/// Visits every node of a file by mutable reference
+pub fn visit_mut<V: VisitorMut + ?Sized>(v: &mut V, file: &mut ProtoFile) {
+    v.visit_file_mut(&mut Context::default(), file);
+}
+
+pub trait Visitor<'ast> {
+    fn visit_file(&mut self, ctx: &mut Context, file: &'ast ProtoFile) {
+        walk_file(self, ctx, file);
+    }
+
+    fn visit_import(&mut self, _ctx: &mut Context, _import: &'ast ProtoImport) {}
+
+    fn visit_option(&mut self, _ctx: &mut Context, _option: &'ast ProtoOption) {}
+
+    fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+        walk_message(self, ctx, message);
+    }
+
+    fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+        walk_field(self, ctx, field);
+    }
+
+    fn visit_field_type(&mut self, _ctx: &mut Context, _field_type: &'ast ProtoFieldType) {}
+
+    fn visit_oneof(&mut self, ctx: &mut Context, oneof: &'ast ProtoOneOf) {
+        walk_oneof(self, ctx, oneof);
+    }
+
+    fn visit_enum(&mut self, ctx: &mut Context, enumeration: &'ast ProtoEnum) {
+        walk_enum(self, ctx, enumeration);
+    }
+
+    fn visit_enum_value(&mut self, ctx: &mut Context, value: &'ast ProtoEnumValue) {
+        walk_enum_value(self, ctx, value);
+    }
+
+    fn visit_service(&mut self, ctx: &mut Context, service: &'ast ProtoService) {
+        walk_service(self, ctx, service);
+    }
+
+    fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+        walk_rpc(self, ctx, rpc);
+    }
+
+    fn visit_rpc_type(&mut self, ctx: &mut Context, rpc_type: &'ast ProtoRpcType) {
+        walk_rpc_type(self, ctx, rpc_type);
+    }
+
+    fn visit_extend(&mut self, ctx: &mut Context, extend: &'ast ProtoExtend) {
+        walk_extend(self, ctx, extend);
+    }
+}
+
+pub fn walk_file<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    file: &'ast ProtoFile,
+) {
+    ctx.package = file.package().to_vec();
+    for import in file.imports() {
+        v.visit_import(ctx, import);
+    }
+    for option in file.options() {
+        v.visit_option(ctx, option);
+    }
+    for message in file.messages() {
+        v.visit_message(ctx, message);
+    }
+    for enumeration in file.enums() {
+        v.visit_enum(ctx, enumeration);
+    }
+    for service in file.services() {
+        v.visit_service(ctx, service);
+    }
+    for extend in file.extends() {
+        v.visit_extend(ctx, extend);
+    }
+}
+
+pub fn walk_message<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    message: &'ast ProtoMessage,
+) {
+    ctx.messages.push(message.name().to_owned());
+    for option in message.options() {
+        v.visit_option(ctx, option);
+    }
+    // Extension ranges declared together share their options, which are visited once
+    let ranges = message
+        .extension_ranges()
+        .chunk_by(|a, b| a.options() == b.options());
+    for option in ranges.flat_map(|group| group[0].options()) {
+        v.visit_option(ctx, option);
+    }
+    for field in message.fields() {
+        v.visit_field(ctx, field);
+    }
+    for oneof in message.oneofs() {
+        v.visit_oneof(ctx, oneof);
+    }
+    for nested in message.messages() {
+        v.visit_message(ctx, nested);
+    }
+    for enumeration in message.enums() {
+        v.visit_enum(ctx, enumeration);
+    }
+    for extend in message.extends() {
+        v.visit_extend(ctx, extend);
+    }
+    ctx.messages.pop();
+}
+
+pub fn walk_field<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    field: &'ast MessageField,
+) {
+    v.visit_field_type(ctx, field.field_type());
+    for option in field.options() {
+        v.visit_option(ctx, option);
+    }
+    if let Some(group) = field.group() {
+        // A group's message is declared alongside its field, outside any oneof or extend
+        let oneof = ctx.oneof.take();
+        let extend = std::mem::take(&mut ctx.extend);
+        v.visit_message(ctx, group);
+        ctx.oneof = oneof;
+        ctx.extend = extend;
+    }
+}
+
+pub fn walk_oneof<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    oneof: &'ast ProtoOneOf,
+) {
+    ctx.oneof = Some(oneof.name().to_owned());
+    for option in oneof.options() {
+        v.visit_option(ctx, option);
+    }
+    for field in oneof.fields() {
+        v.visit_field(ctx, field);
+    }
+    ctx.oneof = None;
+}
+
+pub fn walk_enum<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    enumeration: &'ast ProtoEnum,
+) {
+    ctx.enumeration = Some(enumeration.name().to_owned());
+    for option in enumeration.options() {
+        v.visit_option(ctx, option);
+    }
+    for value in enumeration.values() {
+        v.visit_enum_value(ctx, value);
+    }
+    ctx.enumeration = None;
+}
+
+pub fn walk_enum_value<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    value: &'ast ProtoEnumValue,
+) {
+    for option in value.options() {
+        v.visit_option(ctx, option);
+    }
+}
+
+pub fn walk_service<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    service: &'ast ProtoService,
+) {
+    ctx.service = Some(service.name().to_owned());
+    for option in service.options() {
+        v.visit_option(ctx, option);
+    }
+    for rpc in service.rpcs() {
+        v.visit_rpc(ctx, rpc);
+    }
+    ctx.service = None;
+}
+
+pub fn walk_rpc<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    rpc: &'ast ProtoRpc,
+) {
+    v.visit_rpc_type(ctx, rpc.request());
+    v.visit_rpc_type(ctx, rpc.returns());
+    for option in rpc.options() {
+        v.visit_option(ctx, option);
+    }
+}
+
+pub fn walk_rpc_type<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    rpc_type: &'ast ProtoRpcType,
+) {
+    v.visit_field_type(ctx, rpc_type.message_type());
+}
+
+pub fn walk_extend<'ast, V: Visitor<'ast> + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    extend: &'ast ProtoExtend,
+) {
+    v.visit_field_type(ctx, extend.extendee());
+    ctx.extend = true;
+    for field in extend.fields() {
+        v.visit_field(ctx, field);
+    }
+    ctx.extend = false;
+}
+
+/// Like [`Visitor`] but able to change the nodes it visits. Parts of nodes without a `visit_*`
+/// method of their own, like field & enum value numbers or reserved & extension ranges, are changed
+/// through the `*_mut` accessors of the node holding them, such as
+/// [`MessageField::index_mut`] & [`ProtoMessage::reserved_ranges_mut`].
+pub trait VisitorMut {
+    fn visit_file_mut(&mut self, ctx: &mut Context, file: &mut ProtoFile) {
+        walk_file_mut(self, ctx, file);
+    }
+
+    fn visit_import_mut(&mut self, _ctx: &mut Context, _import: &mut ProtoImport) {}
+
+    fn visit_option_mut(&mut self, _ctx: &mut Context, _option: &mut ProtoOption) {}
+
+    fn visit_message_mut(&mut self, ctx: &mut Context, message: &mut ProtoMessage) {
+        walk_message_mut(self, ctx, message);
+    }
+
+    fn visit_field_mut(&mut self, ctx: &mut Context, field: &mut MessageField) {
+        walk_field_mut(self, ctx, field);
+    }
+
+    fn visit_field_type_mut(&mut self, _ctx: &mut Context, _field_type: &mut ProtoFieldType) {}
+
+    fn visit_oneof_mut(&mut self, ctx: &mut Context, oneof: &mut ProtoOneOf) {
+        walk_oneof_mut(self, ctx, oneof);
+    }
+
+    fn visit_enum_mut(&mut self, ctx: &mut Context, enumeration: &mut ProtoEnum) {
+        walk_enum_mut(self, ctx, enumeration);
+    }
+
+    fn visit_enum_value_mut(&mut self, ctx: &mut Context, value: &mut ProtoEnumValue) {
+        walk_enum_value_mut(self, ctx, value);
+    }
+
+    fn visit_service_mut(&mut self, ctx: &mut Context, service: &mut ProtoService) {
+        walk_service_mut(self, ctx, service);
+    }
+
+    fn visit_rpc_mut(&mut self, ctx: &mut Context, rpc: &mut ProtoRpc) {
+        walk_rpc_mut(self, ctx, rpc);
+    }
+
+    fn visit_rpc_type_mut(&mut self, ctx: &mut Context, rpc_type: &mut ProtoRpcType) {
+        walk_rpc_type_mut(self, ctx, rpc_type);
+    }
+
+    fn visit_extend_mut(&mut self, ctx: &mut Context, extend: &mut ProtoExtend) {
+        walk_extend_mut(self, ctx, extend);
+    }
+}
+
+pub fn walk_file_mut<V: VisitorMut + ?Sized>(v: &mut V, ctx: &mut Context, file: &mut ProtoFile) {
+    ctx.package = file.package().to_vec();
+    for import in file.imports_mut() {
+        v.visit_import_mut(ctx, import);
+    }
+    for option in file.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+    for message in file.messages_mut() {
+        v.visit_message_mut(ctx, message);
+    }
+    for enumeration in file.enums_mut() {
+        v.visit_enum_mut(ctx, enumeration);
+    }
+    for service in file.services_mut() {
+        v.visit_service_mut(ctx, service);
+    }
+    for extend in file.extends_mut() {
+        v.visit_extend_mut(ctx, extend);
+    }
+}
+
+pub fn walk_message_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    message: &mut ProtoMessage,
+) {
+    ctx.messages.push(message.name().to_owned());
+    for option in message.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+    // Each extension range has its own copy of the options it was declared with
+    for range in message.extension_ranges_mut() {
+        for option in range.options_mut() {
+            v.visit_option_mut(ctx, option);
+        }
+    }
+    for field in message.fields_mut() {
+        v.visit_field_mut(ctx, field);
+    }
+    for oneof in message.oneofs_mut() {
+        v.visit_oneof_mut(ctx, oneof);
+    }
+    for nested in message.messages_mut() {
+        v.visit_message_mut(ctx, nested);
+    }
+    for enumeration in message.enums_mut() {
+        v.visit_enum_mut(ctx, enumeration);
+    }
+    for extend in message.extends_mut() {
+        v.visit_extend_mut(ctx, extend);
+    }
+    ctx.messages.pop();
+}
+
+pub fn walk_field_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    field: &mut MessageField,
+) {
+    v.visit_field_type_mut(ctx, field.field_type_mut());
+    for option in field.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+    if let Some(group) = field.group_mut() {
+        let oneof = ctx.oneof.take();
+        let extend = std::mem::take(&mut ctx.extend);
+        v.visit_message_mut(ctx, group);
+        ctx.oneof = oneof;
+        ctx.extend = extend;
+    }
+}
+
+pub fn walk_oneof_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    oneof: &mut ProtoOneOf,
+) {
+    ctx.oneof = Some(oneof.name().to_owned());
+    for option in oneof.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+    for field in oneof.fields_mut() {
+        v.visit_field_mut(ctx, field);
+    }
+    ctx.oneof = None;
+}
+
+pub fn walk_enum_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    enumeration: &mut ProtoEnum,
+) {
+    ctx.enumeration = Some(enumeration.name().to_owned());
+    for option in enumeration.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+    for value in enumeration.values_mut() {
+        v.visit_enum_value_mut(ctx, value);
+    }
+    ctx.enumeration = None;
+}
+
+pub fn walk_enum_value_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    value: &mut ProtoEnumValue,
+) {
+    for option in value.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+}
+
+pub fn walk_service_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    service: &mut ProtoService,
+) {
+    ctx.service = Some(service.name().to_owned());
+    for option in service.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+    for rpc in service.rpcs_mut() {
+        v.visit_rpc_mut(ctx, rpc);
+    }
+    ctx.service = None;
+}
+
+pub fn walk_rpc_mut<V: VisitorMut + ?Sized>(v: &mut V, ctx: &mut Context, rpc: &mut ProtoRpc) {
+    v.visit_rpc_type_mut(ctx, rpc.request_mut());
+    v.visit_rpc_type_mut(ctx, rpc.returns_mut());
+    for option in rpc.options_mut() {
+        v.visit_option_mut(ctx, option);
+    }
+}
+
+pub fn walk_rpc_type_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    rpc_type: &mut ProtoRpcType,
+) {
+    v.visit_field_type_mut(ctx, rpc_type.message_type_mut());
+}
+
+pub fn walk_extend_mut<V: VisitorMut + ?Sized>(
+    v: &mut V,
+    ctx: &mut Context,
+    extend: &mut ProtoExtend,
+) {
+    v.visit_field_type_mut(ctx, extend.extendee_mut());
+    ctx.extend = true;
+    for field in extend.fields_mut() {
+        v.visit_field_mut(ctx, field);
+    }
+    ctx.extend = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"syntax = "proto3";
+package foo.v1;
+
+message Outer {
+    string name = 1;
+    oneof choice {
+        int32 a = 2;
+    }
+    message Inner {
+        Outer outer = 1;
+    }
+    enum Kind {
+        KIND_UNKNOWN = 0;
+    }
+}
+
+service Svc {
+    rpc Get(Outer) returns (Outer.Inner);
+}
+"#;
+
+    #[derive(Default)]
+    struct Names(Vec<String>);
+
+    impl<'ast> Visitor<'ast> for Names {
+        fn visit_message(&mut self, ctx: &mut Context, message: &'ast ProtoMessage) {
+            self.0.push(ctx.full_name(message.name()));
+            walk_message(self, ctx, message);
+        }
+
+        fn visit_field(&mut self, ctx: &mut Context, field: &'ast MessageField) {
+            let name = ctx.full_name(field.name());
+            match ctx.oneof() {
+                Some(oneof) => self.0.push(format!("{name} (oneof {oneof})")),
+                None => self.0.push(name),
+            }
+        }
+
+        fn visit_enum_value(&mut self, ctx: &mut Context, value: &'ast ProtoEnumValue) {
+            self.0.push(ctx.full_name(value.name()));
+        }
+
+        fn visit_rpc(&mut self, ctx: &mut Context, rpc: &'ast ProtoRpc) {
+            let service = ctx.service().unwrap().to_owned();
+            self.0
+                .push(format!("{}.{}", ctx.full_name(&service), rpc.name()));
+        }
+    }
+
+    #[test]
+    fn visit_with_context() {
+        let file = crate::parse(SOURCE).unwrap();
+        let mut names = Names::default();
+        visit(&mut names, &file);
+
+        assert_eq!(
+            vec![
+                "foo.v1.Outer",
+                "foo.v1.Outer.name",
+                "foo.v1.Outer.a (oneof choice)",
+                "foo.v1.Outer.Inner",
+                "foo.v1.Outer.Inner.outer",
+                "foo.v1.Outer.KIND_UNKNOWN",
+                "foo.v1.Svc.Get",
+            ],
+            names.0
+        );
+    }
+
+    struct Rename;
+
+    impl VisitorMut for Rename {
+        fn visit_message_mut(&mut self, ctx: &mut Context, message: &mut ProtoMessage) {
+            if message.name() == "Outer" {
+                *message.name_mut() = "Renamed".to_owned();
+            }
+            walk_message_mut(self, ctx, message);
+        }
+
+        fn visit_field_type_mut(&mut self, _ctx: &mut Context, field_type: &mut ProtoFieldType) {
+            match field_type {
+                ProtoFieldType::Identifier(id) if id == "Outer" => *id = "Renamed".to_owned(),
+                ProtoFieldType::FullIdentifier(parts) if parts[0] == "Outer" => {
+                    parts[0] = "Renamed".to_owned()
+                }
+                _ => {}
+            }
+        }
+    }
+
+    #[test]
+    fn visit_mut_renames() {
+        let mut file = crate::parse(SOURCE).unwrap();
+        visit_mut(&mut Rename, &mut file);
+
+        let mut names = Names::default();
+        visit(&mut names, &file);
+        assert_eq!("foo.v1.Renamed.Inner", names.0[3]);
+
+        let inner = &file.messages()[0].messages()[0];
+        assert_eq!(
+            &ProtoFieldType::Identifier("Renamed".into()),
+            inner.fields()[0].field_type()
+        );
+        let rpc = &file.services()[0].rpcs()[0];
+        assert_eq!(
+            &ProtoFieldType::FullIdentifier(vec!["Renamed".into(), "Inner".into()]),
+            rpc.returns().message_type()
+        );
+    }
+
+    // Renumber offsets field & enum value numbers along with the ranges reserving them
+    struct Renumber(i32);
+
+    impl VisitorMut for Renumber {
+        fn visit_message_mut(&mut self, ctx: &mut Context, message: &mut ProtoMessage) {
+            for range in message.reserved_ranges_mut() {
+                *range.start_mut() += i64::from(self.0);
+                *range.end_mut() += i64::from(self.0);
+            }
+            walk_message_mut(self, ctx, message);
+        }
+
+        fn visit_field_mut(&mut self, ctx: &mut Context, field: &mut MessageField) {
+            *field.index_mut() += self.0.unsigned_abs();
+            walk_field_mut(self, ctx, field);
+        }
+
+        fn visit_enum_value_mut(&mut self, _ctx: &mut Context, value: &mut ProtoEnumValue) {
+            *value.number_mut() += self.0;
+        }
+    }
+
+    #[test]
+    fn visit_mut_renumbers() {
+        let mut file = crate::parse(
+            "syntax = \"proto3\"; message Foo { reserved 2 to 3; string a = 1; \
+             enum Kind { KIND_UNKNOWN = 0; } }",
+        )
+        .unwrap();
+        visit_mut(&mut Renumber(10), &mut file);
+
+        let message = &file.messages()[0];
+        let range = &message.reserved_ranges()[0];
+        assert_eq!((12, 13), (range.start(), range.end()));
+        assert_eq!(11, message.fields()[0].index());
+        assert_eq!(10, message.enums()[0].values()[0].number());
+    }
+}
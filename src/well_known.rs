@@ -0,0 +1,80 @@
+//! The well-known types & `descriptor.proto` from protobuf, built in so imports of them resolve
+//! without protoc installed or an include path containing them.
+
+use std::sync::OnceLock;
+
+use crate::ast::ProtoFile;
+
+macro_rules! files {
+    ($($name:literal),* $(,)?) => {
+        [$((
+            concat!("google/protobuf/", $name),
+            include_str!(concat!("../proto/google/protobuf/", $name)),
+        )),*]
+    };
+}
+
+const FILES: [(&str, &str); 11] = files![
+    "any.proto",
+    "api.proto",
+    "descriptor.proto",
+    "duration.proto",
+    "empty.proto",
+    "field_mask.proto",
+    "source_context.proto",
+    "struct.proto",
+    "timestamp.proto",
+    "type.proto",
+    "wrappers.proto",
+];
+
+/// The source of the built in file `name`, such as `google/protobuf/timestamp.proto`
+pub fn source(name: &str) -> Option<&'static str> {
+    FILES
+        .iter()
+        .find(|(file, _)| *file == name)
+        .map(|(_, source)| *source)
+}
+
+/// The built in `descriptor.proto`, which declares the options messages like `FileOptions`
+pub fn descriptor() -> &'static ProtoFile {
+    static DESCRIPTOR: OnceLock<ProtoFile> = OnceLock::new();
+    DESCRIPTOR.get_or_init(|| {
+        let source =
+            source("google/protobuf/descriptor.proto").expect("descriptor.proto is built in");
+        crate::parse(source).expect("the built in descriptor.proto parses")
+    })
+}
+
+/// The names of every built in file
+pub fn names() -> impl Iterator<Item = &'static str> {
+    FILES.iter().map(|(name, _)| *name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::workspace::Workspace;
+
+    #[test]
+    fn files_are_valid() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        for name in super::names() {
+            workspace.load(name).unwrap();
+        }
+        for name in super::names() {
+            assert!(workspace.file(name).is_some(), "{name} didn't parse");
+            assert_eq!(
+                Vec::<String>::new(),
+                workspace
+                    .diagnostics(name)
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>(),
+                "{name}"
+            );
+        }
+        assert!(super::source("google/protobuf/missing.proto").is_none());
+    }
+}
@@ -0,0 +1,906 @@
+//! Files loaded together so they can use each other's declarations.
+//!
+//! Files are named by their import path, `foo/bar/baz.proto`, which is found within an ordered list
+//! of include paths in the same way as protoc's `--proto_path`. The first include path containing
+//! the file is used, falling back to the [built in](crate::well_known) well-known types.
+//!
+//! A file can use the declarations of the files it imports directly, along with those publicly
+//! imported by them. Weak imports may be missing.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+
+use crate::ast::{ProtoFile, ProtoImport, ProtoImportType};
+use crate::diagnostics::Diagnostic;
+use crate::parser;
+use crate::symbols::{Symbol, SymbolKind, SymbolTable, VisibleSymbols};
+use crate::validate::{self, join, LayoutChecks};
+use crate::well_known;
+
+#[derive(Debug, Default, Clone)]
+pub struct Workspace {
+    include_paths: Vec<PathBuf>,
+    files: BTreeMap<String, WorkspaceFile>,
+    symbols: SymbolTable,
+    layout_checks: LayoutChecks,
+    // Why imports which aren't loaded couldn't be, by file name
+    load_errors: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+struct WorkspaceFile {
+    // Where the file was read from, `None` when it was opened with its source or is built in
+    path: Option<PathBuf>,
+    file: Option<ProtoFile>,
+    // Syntax problems, the rest depend on the other files so are found as they're asked for
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Workspace {
+    pub fn new<I, P>(include_paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+    {
+        Self {
+            include_paths: include_paths.into_iter().map(Into::into).collect(),
+            ..Self::default()
+        }
+    }
+
+    pub fn include_paths(&self) -> &[PathBuf] {
+        &self.include_paths
+    }
+
+    /// Which checks on how files & packages are laid out [`diagnostics`](Self::diagnostics) runs
+    pub fn layout_checks(&self) -> LayoutChecks {
+        self.layout_checks
+    }
+
+    pub fn set_layout_checks(&mut self, checks: LayoutChecks) {
+        self.layout_checks = checks;
+    }
+
+    /// Every path the file `name` is found at, in the order of the include paths
+    pub fn find_file(&self, name: &str) -> Vec<PathBuf> {
+        self.include_paths
+            .iter()
+            .map(|include_path| include_path.join(name))
+            .filter(|path| path.is_file())
+            .collect()
+    }
+
+    /// The name of the file at `path` relative to the first include path containing it
+    pub fn file_name(&self, path: &Path) -> Option<String> {
+        self.include_paths.iter().find_map(|include_path| {
+            let relative = path.strip_prefix(include_path).ok()?;
+            let parts: Option<Vec<_>> = relative.iter().map(|part| part.to_str()).collect();
+            Some(parts?.join("/"))
+        })
+    }
+
+    /// Loads the file `name` from the include paths along with its imports, unless it's already
+    /// loaded
+    pub fn load(&mut self, name: &str) -> Result<()> {
+        if self.files.contains_key(name) {
+            return Ok(());
+        }
+        if let Some(path) = self.find_file(name).into_iter().next() {
+            let source = match fs::read_to_string(&path) {
+                Ok(source) => source,
+                Err(err) => bail!("{} couldn't be read, {err}", path.display()),
+            };
+            self.insert(name, Some(path), &source);
+        } else if let Some(source) = well_known::source(name) {
+            self.insert(name, None, source);
+        } else {
+            bail!("{name} wasn't found in any include path");
+        }
+        Ok(())
+    }
+
+    /// Adds the file `name` with `source`, replacing any already loaded, & loads its imports
+    pub fn open(&mut self, name: &str, source: &str) {
+        self.insert(name, None, source);
+    }
+
+    /// Removes the file `name`, the files it imported are kept. When other files import it, it's
+    /// loaded again from the include paths for them.
+    pub fn close(&mut self, name: &str) {
+        self.files.remove(name);
+        self.symbols.remove_file(name);
+        let imported = self
+            .files
+            .keys()
+            .any(|other| self.imports(other).any(|import| import.path() == name));
+        if imported {
+            self.load_import(name);
+        }
+    }
+
+    pub fn file(&self, name: &str) -> Option<&ProtoFile> {
+        self.files.get(name)?.file.as_ref()
+    }
+
+    /// Where the file `name` was read from, `None` when it was opened with its source, is built in
+    /// or isn't loaded
+    pub fn path(&self, name: &str) -> Option<&Path> {
+        self.files.get(name)?.path.as_deref()
+    }
+
+    /// The names of every loaded file
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(String::as_str)
+    }
+
+    /// Every symbol declared within the loaded files
+    pub fn symbols(&self) -> &SymbolTable {
+        &self.symbols
+    }
+
+    /// The symbols the file `name` can use, those declared in the file itself, its imports & the
+    /// files they publicly import
+    pub fn visible_symbols(&self, name: &str) -> VisibleSymbols<'_> {
+        let mut visible = HashSet::from([name]);
+        for import in self.imports(name) {
+            visible.extend(self.exported(import.path()));
+        }
+
+        // The rest of the files imported by imports can't be used, but are worth mentioning when
+        // a name is declared in one
+        let mut indirect = HashMap::new();
+        for import in self.imports(name) {
+            let mut pending: Vec<_> = self.imports(import.path()).map(ProtoImport::path).collect();
+            while let Some(path) = pending.pop() {
+                if visible.contains(path) || indirect.contains_key(path) {
+                    continue;
+                }
+                indirect.insert(path.to_owned(), import.path().to_owned());
+                pending.extend(self.imports(path).map(ProtoImport::path));
+            }
+        }
+        self.symbols.visible_to(visible).with_indirect(indirect)
+    }
+
+    /// Every problem found within the file `name`
+    pub fn diagnostics(&self, name: &str) -> Vec<Diagnostic> {
+        let Some(loaded) = self.files.get(name) else {
+            return vec![];
+        };
+        let mut diagnostics = loaded.diagnostics.clone();
+        diagnostics.extend(self.import_problems(name));
+        let mut cyclic = HashSet::new();
+        for import in self.imports(name) {
+            let mut chain = vec![name, import.path()];
+            if import.path() == name
+                || self.find_cycle(name, &mut chain, &mut HashSet::from([import.path()]))
+            {
+                cyclic.insert(import.path());
+                diagnostics.push(Diagnostic::error(
+                    import.span(),
+                    format!("import cycle {}", chain.join(" -> ")),
+                ));
+            }
+        }
+        let Some(file) = &loaded.file else {
+            return diagnostics;
+        };
+
+        let symbols = self.visible_symbols(name);
+        let used = validate::used_files(file, &symbols);
+        for import in self.imports(name) {
+            // Public imports are for the files importing this one, missing & cyclic imports are
+            // already reported
+            if *import.import_type() == ProtoImportType::Public
+                || cyclic.contains(import.path())
+                || self.file(import.path()).is_none()
+            {
+                continue;
+            }
+            if !self
+                .exported(import.path())
+                .iter()
+                .any(|exported| used.contains(*exported))
+            {
+                diagnostics.push(Diagnostic::warning(
+                    import.span(),
+                    format!(
+                        "unused import {}, none of its declarations are used",
+                        import.path()
+                    ),
+                ));
+            }
+        }
+        diagnostics.extend(self.duplicate_symbols(name));
+        diagnostics.extend(self.extension_conflicts(name));
+        let others = self
+            .files
+            .iter()
+            .filter_map(|(name, loaded)| Some((name.as_str(), loaded.file.as_ref()?)));
+        diagnostics.extend(validate::check_layout(
+            name,
+            file,
+            others,
+            self.layout_checks,
+        ));
+        diagnostics.extend(validate::validate(file, &symbols));
+        diagnostics
+    }
+
+    // import_problems reports the imports of the file `name` which aren't loaded, along with those
+    // found in more than one include path. They're found against the files loaded now, as opening
+    // & closing files changes them.
+    fn import_problems(&self, name: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+        for import in self.imports(name) {
+            let path = import.path();
+            if !self.files.contains_key(path) {
+                // Weak imports may be missing
+                if *import.import_type() != ProtoImportType::Weak {
+                    let message = self
+                        .load_errors
+                        .get(path)
+                        .cloned()
+                        .unwrap_or_else(|| format!("{path} wasn't found in any include path"));
+                    diagnostics.push(Diagnostic::error(import.span(), message));
+                }
+                continue;
+            }
+            if let [used, shadowed @ ..] = self.find_file(path).as_slice() {
+                if shadowed.is_empty() {
+                    continue;
+                }
+                let shadowed: Vec<_> = shadowed
+                    .iter()
+                    .map(|path| path.display().to_string())
+                    .collect();
+                diagnostics.push(Diagnostic::warning(
+                    import.span(),
+                    format!(
+                        "{} is used, shadowing {}",
+                        used.display(),
+                        shadowed.join(" & ")
+                    ),
+                ));
+            }
+        }
+        diagnostics
+    }
+
+    // duplicate_symbols reports the declarations in the file `name` whose full names other loaded
+    // files declare too, whether or not they're imported together. Only the outermost duplicate is
+    // reported, the declarations within a duplicated message are duplicates too.
+    fn duplicate_symbols(&self, name: &str) -> Vec<Diagnostic> {
+        // Packages can be declared by any number of files, but nothing else can share their names
+        let elsewhere = |symbol: &Symbol| -> Vec<&Symbol> {
+            self.symbols
+                .definitions(symbol.full_name())
+                .iter()
+                .filter(|other| {
+                    other.file() != name
+                        && (symbol.kind() != SymbolKind::Package
+                            || other.kind() != SymbolKind::Package)
+                })
+                .collect()
+        };
+
+        let mut diagnostics = vec![];
+        for symbol in self.symbols.file_symbols(name) {
+            if symbol.kind() == SymbolKind::Package {
+                continue;
+            }
+            let others = elsewhere(symbol);
+            let parent_duplicated = self
+                .symbols
+                .definitions(symbol.parent())
+                .iter()
+                .find(|parent| parent.file() == name)
+                .is_some_and(|parent| !elsewhere(parent).is_empty());
+            if others.is_empty() || parent_duplicated {
+                continue;
+            }
+
+            let mut files: Vec<_> = others.iter().map(|other| other.file()).collect();
+            files.dedup();
+            let mut diagnostic = Diagnostic::error(
+                symbol.span(),
+                format!(
+                    "{} is also declared in {}, full names must be unique across every file",
+                    symbol.full_name(),
+                    join(&files)
+                ),
+            );
+            for other in others {
+                // Packages don't have a span of their own
+                let span = match other.kind() {
+                    SymbolKind::Package => self
+                        .file(other.file())
+                        .map_or_else(Default::default, ProtoFile::package_span),
+                    _ => other.span(),
+                };
+                diagnostic = diagnostic.with_related_in(
+                    other.file(),
+                    span,
+                    format!("{} declares {} here", other.file(), other.full_name()),
+                );
+            }
+            diagnostics.push(diagnostic);
+        }
+        diagnostics
+    }
+
+    // extension_conflicts reports the extensions in the file `name` using a number another loaded
+    // file uses to extend the same message, which protoc only finds when both are compiled together
+    fn extension_conflicts(&self, name: &str) -> Vec<Diagnostic> {
+        // The full name of the message an extension extends along with its number
+        let key = |symbol: &Symbol| {
+            let extendee = symbol.extendee()?.name();
+            let extendee = self.symbols.resolve_type(symbol.scope(), &extendee).ok()?;
+            Some((extendee.full_name().to_owned(), symbol.number()?))
+        };
+        let extensions = || {
+            self.symbols
+                .iter()
+                .filter(|symbol| symbol.kind() == SymbolKind::Extension)
+        };
+
+        let mut others = HashMap::new();
+        for symbol in extensions().filter(|symbol| symbol.file() != name) {
+            if let Some(key) = key(symbol) {
+                others.entry(key).or_insert(symbol);
+            }
+        }
+        let mut diagnostics = vec![];
+        for symbol in extensions().filter(|symbol| symbol.file() == name) {
+            let Some((extendee, number)) = key(symbol) else {
+                continue;
+            };
+            let Some(other) = others.get(&(extendee.clone(), number)) else {
+                continue;
+            };
+            diagnostics.push(
+                Diagnostic::error(
+                    symbol.span(),
+                    format!(
+                        "extension number {number} of {extendee} is already used by {} in {}",
+                        other.full_name(),
+                        other.file()
+                    ),
+                )
+                .with_related_in(
+                    other.file(),
+                    other.span(),
+                    format!("{} uses {number} here", other.full_name()),
+                ),
+            );
+        }
+        diagnostics
+    }
+
+    // exported is the files whose declarations importing the file `name` makes visible, the file
+    // itself along with those it imports publicly
+    fn exported<'a>(&'a self, name: &'a str) -> HashSet<&'a str> {
+        let mut exported = HashSet::new();
+        let mut pending = vec![name];
+        while let Some(name) = pending.pop() {
+            if exported.insert(name) {
+                pending.extend(
+                    self.imports(name)
+                        .filter(|import| *import.import_type() == ProtoImportType::Public)
+                        .map(ProtoImport::path),
+                );
+            }
+        }
+        exported
+    }
+
+    fn imports<'a>(&'a self, name: &str) -> impl Iterator<Item = &'a ProtoImport> {
+        self.file(name).into_iter().flat_map(ProtoFile::imports)
+    }
+
+    // find_cycle searches the imports of the last file in chain for a path back to its first
+    // file, leaving the path in chain when one is found
+    fn find_cycle<'a>(
+        &'a self,
+        target: &str,
+        chain: &mut Vec<&'a str>,
+        visited: &mut HashSet<&'a str>,
+    ) -> bool {
+        let current = chain[chain.len() - 1];
+        for import in self.imports(current) {
+            let path = import.path();
+            if path == target {
+                chain.push(path);
+                return true;
+            }
+            if visited.insert(path) {
+                chain.push(path);
+                if self.find_cycle(target, chain, visited) {
+                    return true;
+                }
+                chain.pop();
+            }
+        }
+        false
+    }
+
+    fn insert(&mut self, name: &str, path: Option<PathBuf>, source: &str) {
+        let (file, diagnostics) = parser::parse_source(source);
+        let mut imports = vec![];
+        if let Some(file) = &file {
+            self.symbols.add_file(name, file);
+            imports.extend(file.imports().iter().map(|import| import.path().to_owned()));
+        } else {
+            self.symbols.remove_file(name);
+        }
+
+        self.files.insert(
+            name.to_owned(),
+            WorkspaceFile {
+                path,
+                file,
+                diagnostics,
+            },
+        );
+        // Files already loaded are skipped, which stops a cycle of imports loading forever
+        for import in imports {
+            self.load_import(&import);
+        }
+    }
+
+    // load_import loads a file imported by another, keeping why it couldn't be loaded to report
+    // on the import
+    fn load_import(&mut self, name: &str) {
+        match self.load(name) {
+            Ok(()) => {
+                self.load_errors.remove(name);
+            }
+            Err(err) => {
+                self.load_errors.insert(name.to_owned(), err.to_string());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // TempDir is a directory of files removed when it's dropped
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str, files: &[(&str, &str)]) -> Self {
+            let dir = std::env::temp_dir().join(format!("proto_ls-{}-{name}", std::process::id()));
+            for (name, source) in files {
+                let path = dir.join(name);
+                fs::create_dir_all(path.parent().unwrap()).unwrap();
+                fs::write(path, source).unwrap();
+            }
+            Self(dir)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn messages(diagnostics: Vec<Diagnostic>) -> Vec<String> {
+        diagnostics.iter().map(ToString::to_string).collect()
+    }
+
+    #[test]
+    fn include_paths() {
+        let first = TempDir::new(
+            "include-first",
+            &[(
+                "foo/bar.proto",
+                "syntax = \"proto3\"; package foo; message Bar {}",
+            )],
+        );
+        let second = TempDir::new(
+            "include-second",
+            &[
+                (
+                    "foo/bar.proto",
+                    "syntax = \"proto3\"; package foo; message Shadowed {}",
+                ),
+                (
+                    "foo/baz.proto",
+                    "syntax = \"proto3\"; package foo; message Baz {}",
+                ),
+            ],
+        );
+        let mut workspace = Workspace::new([&first.0, &second.0]);
+
+        workspace.open(
+            "main.proto",
+            r#"syntax = "proto3";
+import "foo/bar.proto";
+import "foo/baz.proto";
+import "foo/missing.proto";
+message Main {
+    foo.Bar bar = 1;
+    foo.Baz baz = 2;
+    foo.Shadowed shadowed = 3;
+}"#,
+        );
+
+        assert_eq!(
+            vec![
+                format!(
+                    "2:1: warning: {} is used, shadowing {}",
+                    first.0.join("foo/bar.proto").display(),
+                    second.0.join("foo/bar.proto").display()
+                ),
+                "4:1: error: foo/missing.proto wasn't found in any include path".into(),
+                "8:5: error: unresolved type foo.Shadowed, tried Main.foo.Shadowed & foo.Shadowed"
+                    .into(),
+            ],
+            messages(workspace.diagnostics("main.proto"))
+        );
+        assert_eq!(
+            Some(first.0.join("foo/bar.proto").as_path()),
+            workspace.path("foo/bar.proto")
+        );
+        assert_eq!(
+            vec!["foo/bar.proto", "foo/baz.proto", "main.proto"],
+            workspace.file_names().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            Some("foo/baz.proto".into()),
+            workspace.file_name(&second.0.join("foo/baz.proto"))
+        );
+    }
+
+    #[test]
+    fn direct_imports_only() {
+        let dir = TempDir::new(
+            "direct-imports",
+            &[
+                (
+                    "a.proto",
+                    "syntax = \"proto3\"; import \"b.proto\"; message A { B b = 1; C c = 2; }",
+                ),
+                (
+                    "b.proto",
+                    "syntax = \"proto3\"; import \"c.proto\"; message B { C c = 1; }",
+                ),
+                ("c.proto", "syntax = \"proto3\"; message C {}"),
+            ],
+        );
+        let mut workspace = Workspace::new([&dir.0]);
+        workspace.load("a.proto").unwrap();
+
+        assert_eq!(
+            vec![
+                "1:59: error: unresolved type C, tried A.C & C; it's declared in c.proto, which is \
+                 only imported indirectly through b.proto, import it directly"
+            ],
+            messages(workspace.diagnostics("a.proto"))
+        );
+        assert!(workspace.diagnostics("b.proto").is_empty());
+        assert!(workspace.load("missing.proto").is_err());
+    }
+
+    #[test]
+    fn public_and_weak_imports() {
+        let dir = TempDir::new(
+            "public-imports",
+            &[
+                (
+                    "a.proto",
+                    r#"syntax = "proto3";
+import "b.proto";
+import weak "missing.proto";
+message A { B b = 1; C c = 2; D d = 3; E e = 4; }"#,
+                ),
+                (
+                    "b.proto",
+                    r#"syntax = "proto3"; import public "c.proto"; import "e.proto"; message B {}"#,
+                ),
+                (
+                    "c.proto",
+                    r#"syntax = "proto3"; import public "d.proto"; message C {}"#,
+                ),
+                ("d.proto", r#"syntax = "proto3"; message D {}"#),
+                ("e.proto", r#"syntax = "proto3"; message E {}"#),
+            ],
+        );
+        let mut workspace = Workspace::new([&dir.0]);
+        workspace.load("a.proto").unwrap();
+
+        assert_eq!(
+            vec![
+                "4:40: error: unresolved type E, tried A.E & E; it's declared in e.proto, which is \
+                 only imported indirectly through b.proto, import it directly"
+            ],
+            messages(workspace.diagnostics("a.proto"))
+        );
+    }
+
+    #[test]
+    fn import_problems_follow_the_loaded_files() {
+        let first = TempDir::new(
+            "problems-first",
+            &[("shared.proto", "syntax = \"proto3\"; message Shared {}")],
+        );
+        let second = TempDir::new(
+            "problems-second",
+            &[("shared.proto", "syntax = \"proto3\"; message Shared {}")],
+        );
+        fs::write(first.0.join("binary.proto"), [0xff, 0xfe]).unwrap();
+        let mut workspace = Workspace::new([&first.0, &second.0]);
+        workspace.open("dep.proto", "syntax = \"proto3\"; message Dep {}");
+        workspace.open(
+            "main.proto",
+            r#"syntax = "proto3";
+import "dep.proto";
+import "shared.proto";
+import "binary.proto";
+message Main {
+    Dep dep = 1;
+    Shared shared = 2;
+}"#,
+        );
+
+        let shadowing = format!(
+            "3:1: warning: {} is used, shadowing {}",
+            first.0.join("shared.proto").display(),
+            second.0.join("shared.proto").display()
+        );
+        let unreadable = format!(
+            "4:1: error: {} couldn't be read, stream did not contain valid UTF-8",
+            first.0.join("binary.proto").display()
+        );
+        assert_eq!(
+            vec![shadowing, unreadable.clone()],
+            messages(workspace.diagnostics("main.proto"))
+        );
+
+        // Closing an import that isn't saved anywhere leaves it missing, & the shadowed copy being
+        // removed ends the warning
+        workspace.close("dep.proto");
+        fs::remove_file(second.0.join("shared.proto")).unwrap();
+        assert_eq!(
+            vec![
+                "2:1: error: dep.proto wasn't found in any include path".to_owned(),
+                unreadable,
+                "6:5: error: unresolved type Dep, tried Main.Dep & Dep".to_owned(),
+            ],
+            messages(workspace.diagnostics("main.proto"))
+        );
+
+        workspace.open("dep.proto", "syntax = \"proto3\"; message Dep {}");
+        assert_eq!(1, workspace.diagnostics("main.proto").len());
+    }
+
+    #[test]
+    fn built_in_fallback() {
+        let dir = TempDir::new(
+            "built-in",
+            &[(
+                "google/protobuf/empty.proto",
+                "syntax = \"proto3\"; package google.protobuf; message Local {}",
+            )],
+        );
+        let mut workspace = Workspace::new([&dir.0]);
+        workspace.open(
+            "main.proto",
+            r#"syntax = "proto3";
+import "google/protobuf/timestamp.proto";
+import "google/protobuf/empty.proto";
+message Main {
+    google.protobuf.Timestamp at = 1;
+    google.protobuf.Local local = 2;
+}"#,
+        );
+
+        assert!(workspace.diagnostics("main.proto").is_empty());
+        assert!(workspace.file("google/protobuf/timestamp.proto").is_some());
+        assert_eq!(None, workspace.path("google/protobuf/timestamp.proto"));
+        // Include paths come first
+        assert!(workspace.path("google/protobuf/empty.proto").is_some());
+    }
+
+    #[test]
+    fn import_cycles() {
+        let dir = TempDir::new(
+            "import-cycles",
+            &[
+                ("a.proto", r#"syntax = "proto3"; import "b.proto";"#),
+                (
+                    "b.proto",
+                    r#"syntax = "proto3"; import "d.proto"; import "c.proto";"#,
+                ),
+                ("c.proto", r#"syntax = "proto3"; import "a.proto";"#),
+                ("d.proto", r#"syntax = "proto3";"#),
+                ("self.proto", r#"syntax = "proto3"; import "self.proto";"#),
+            ],
+        );
+        let mut workspace = Workspace::new([&dir.0]);
+        workspace.load("a.proto").unwrap();
+        workspace.load("self.proto").unwrap();
+
+        assert_eq!(
+            vec!["1:20: error: import cycle a.proto -> b.proto -> c.proto -> a.proto"],
+            messages(workspace.diagnostics("a.proto"))
+        );
+        assert_eq!(
+            vec!["1:20: error: import cycle c.proto -> a.proto -> b.proto -> c.proto"],
+            messages(workspace.diagnostics("c.proto"))
+        );
+        assert!(workspace.diagnostics("d.proto").is_empty());
+        assert_eq!(
+            vec!["1:20: error: import cycle self.proto -> self.proto"],
+            messages(workspace.diagnostics("self.proto"))
+        );
+    }
+
+    #[test]
+    fn unused_imports() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        for (name, source) in [
+            (
+                "types.proto",
+                r#"syntax = "proto3"; package types; message T {}"#,
+            ),
+            (
+                "unused.proto",
+                r#"syntax = "proto3"; package unused; message U {}"#,
+            ),
+            (
+                "options.proto",
+                r#"syntax = "proto3"; package opts;
+import "google/protobuf/descriptor.proto";
+extend google.protobuf.FieldOptions { Rule rule = 50000; }
+extend google.protobuf.MessageOptions { bool skip = 50000; }
+message Rule { int32 min = 1; }"#,
+            ),
+            (
+                "nested.proto",
+                r#"syntax = "proto3"; package nested;
+import "google/protobuf/descriptor.proto";
+extend google.protobuf.FieldOptions { int32 max = 50001; }"#,
+            ),
+            (
+                "reexport.proto",
+                r#"syntax = "proto3"; import public "types.proto";"#,
+            ),
+            (
+                "alone.proto",
+                r#"syntax = "proto3"; package alone; message L {}"#,
+            ),
+        ] {
+            workspace.open(name, source);
+        }
+        workspace.open(
+            "main.proto",
+            r#"syntax = "proto3";
+import "reexport.proto";
+import "unused.proto";
+import "options.proto";
+import public "nested.proto";
+message Main {
+    option (opts.skip) = true;
+    types.T t = 1;
+    alone.L l = 2;
+}"#,
+        );
+
+        assert_eq!(
+            vec![
+                "3:1: warning: unused import unused.proto, none of its declarations are used",
+                "9:5: error: unresolved type alone.L, tried Main.alone.L & alone.L; it's declared \
+                 in alone.proto, which isn't imported",
+            ],
+            messages(workspace.diagnostics("main.proto"))
+        );
+    }
+
+    #[test]
+    fn extension_conflicts() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        workspace.open(
+            "a.proto",
+            r#"syntax = "proto3"; package a;
+import "google/protobuf/descriptor.proto";
+extend google.protobuf.FieldOptions { string rule = 50000; }"#,
+        );
+        workspace.open(
+            "b/b.proto",
+            r#"syntax = "proto3"; package b;
+import "google/protobuf/descriptor.proto";
+extend google.protobuf.FieldOptions {
+    string other = 50000;
+    string unique = 50001;
+}
+extend google.protobuf.MessageOptions { string message = 50000; }"#,
+        );
+
+        let diagnostics = workspace.diagnostics("b/b.proto");
+        assert_eq!(
+            vec![
+                "4:5: error: extension number 50000 of google.protobuf.FieldOptions is already \
+                 used by a.rule in a.proto"
+            ],
+            messages(diagnostics.clone())
+        );
+        let related = &diagnostics[0].related()[0];
+        assert_eq!(
+            (Some("a.proto"), "3:39", "a.rule uses 50000 here"),
+            (
+                related.file(),
+                related.span().start().to_string().as_str(),
+                related.message()
+            )
+        );
+    }
+
+    #[test]
+    fn duplicate_symbols() {
+        let mut workspace = Workspace::new(Vec::<PathBuf>::new());
+        workspace.open(
+            "a/test.proto",
+            r#"syntax = "proto3"; package foo.bar.v1;
+message TestMessage { int32 a = 1; }"#,
+        );
+        workspace.open(
+            "b/test.proto",
+            r#"syntax = "proto3";
+package foo.bar.v1;
+message TestMessage {
+    int32 a = 1;
+    message Inner {}
+}
+message Unique {}"#,
+        );
+        workspace.open(
+            "c.proto",
+            r#"syntax = "proto3"; package foo; message bar {}"#,
+        );
+
+        let related = |name| -> Vec<String> {
+            workspace
+                .diagnostics(name)
+                .iter()
+                .map(|diagnostic| {
+                    let related: Vec<_> = diagnostic
+                        .related()
+                        .iter()
+                        .map(|related| {
+                            format!(
+                                "{}:{}: {}",
+                                related.file().unwrap_or_default(),
+                                related.span().start(),
+                                related.message()
+                            )
+                        })
+                        .collect();
+                    format!("{diagnostic} ({})", related.join(", "))
+                })
+                .collect()
+        };
+        assert_eq!(
+            vec![
+                "3:1: error: foo.bar.v1.TestMessage is also declared in a/test.proto, full names \
+                 must be unique across every file (a/test.proto:2:1: a/test.proto declares \
+                 foo.bar.v1.TestMessage here)"
+            ],
+            related("b/test.proto")
+        );
+        assert_eq!(
+            vec![
+                "1:33: error: foo.bar is also declared in a/test.proto & b/test.proto, full names \
+                 must be unique across every file (a/test.proto:1:20: a/test.proto declares \
+                 foo.bar here, b/test.proto:2:1: b/test.proto declares foo.bar here)"
+            ],
+            related("c.proto")
+        );
+    }
+}